@@ -1,22 +1,34 @@
 //! LEP format decoder.
 //!
 //! Decodes LEP binary format back into `ReplayData`.
-//! Automatically handles zstd decompression (mandatory).
+//!
+//! Files from v3 on start with an uncompressed header naming the codec of the
+//! sections, and are decompressed accordingly. Older files are compressed
+//! whole with zstd.
+//!
+//! The version byte selects the layout: v2 and v3 files are a list of tagged
+//! sections (see the encoder), v1 files use the former fixed layout.
 
-use std::io::Cursor;
+use std::io::{Cursor, Read};
 
 use crate::codec::traits::Decoder;
 use crate::error::{LeptonError, LeptonResult};
-use crate::model::{ReplayData, ReplayInput};
-
+use crate::model::{
+    HealthPoint, JudgementCount, RecordedJudgement, ReplayData, ReplayInput, ReplayMetadata,
+    ScoreSummary,
+};
+
+use super::encoder::{
+    CODEC_NONE, CODEC_ZSTD, CODEC_ZSTD_DICTIONARY, MAGIC, META_GAME, META_GAME_VERSION,
+    META_HEALTH, META_MODS, META_ONLINE_ID, META_PLAYER, META_SCORE, META_TIMESTAMP,
+    SECTION_CHECKSUM, SECTION_EXTENSION, SECTION_HEADER, SECTION_INPUTS, SECTION_JUDGEMENTS,
+    SECTION_METADATA,
+};
 use super::leb128_utils::read_leb128;
 
 /// LEP format decoder.
 pub struct LepDecoder;
 
-/// Magic bytes for LEP format: "LEP\0"
-const MAGIC: &[u8; 4] = b"LEP\0";
-
 /// Reads and validates magic bytes from data.
 fn read_magic(data: &[u8], cursor: &mut Cursor<&[u8]>) -> LeptonResult<()> {
     let mut magic_buf = [0u8; 4];
@@ -90,33 +102,377 @@ fn read_inputs(data: &[u8], cursor: &mut Cursor<&[u8]>) -> LeptonResult<Vec<Repl
     Ok(inputs)
 }
 
+/// Reads a string (LEB128 length + UTF-8 bytes) from data.
+pub(super) fn read_string(data: &[u8], cursor: &mut Cursor<&[u8]>) -> LeptonResult<String> {
+    let len = read_leb128(cursor)? as usize;
+    if cursor.position() + len as u64 > data.len() as u64 {
+        return Err(LeptonError::InvalidMagic);
+    }
+    let bytes = &data[cursor.position() as usize..cursor.position() as usize + len];
+    cursor.set_position(cursor.position() + len as u64);
+    Ok(String::from_utf8(bytes.to_vec())?)
+}
+
+/// Reads a single byte from data.
+pub(super) fn read_u8(data: &[u8], cursor: &mut Cursor<&[u8]>) -> LeptonResult<u8> {
+    if cursor.position() + 1 > data.len() as u64 {
+        return Err(LeptonError::InvalidMagic);
+    }
+    let value = data[cursor.position() as usize];
+    cursor.set_position(cursor.position() + 1);
+    Ok(value)
+}
+
+/// Reads a little-endian i64 from data.
+fn read_i64(data: &[u8], cursor: &mut Cursor<&[u8]>) -> LeptonResult<i64> {
+    if cursor.position() + 8 > data.len() as u64 {
+        return Err(LeptonError::InvalidMagic);
+    }
+    let bytes: [u8; 8] = data[cursor.position() as usize..cursor.position() as usize + 8]
+        .try_into()
+        .map_err(|_| LeptonError::InvalidMagic)?;
+    cursor.set_position(cursor.position() + 8);
+    Ok(i64::from_le_bytes(bytes))
+}
+
+/// Reads the optional metadata block from data.
+/// Replays written without metadata end right after the inputs.
+fn read_metadata(data: &[u8], cursor: &mut Cursor<&[u8]>) -> LeptonResult<ReplayMetadata> {
+    if cursor.position() >= data.len() as u64 {
+        return Ok(ReplayMetadata::default());
+    }
+
+    let flags = read_u8(data, cursor)?;
+    let mut metadata = ReplayMetadata::default();
+
+    if flags & META_PLAYER != 0 {
+        metadata.player = Some(read_string(data, cursor)?);
+    }
+    if flags & META_TIMESTAMP != 0 {
+        metadata.timestamp_ns = Some(read_i64(data, cursor)?);
+    }
+    if flags & META_GAME != 0 {
+        metadata.game = Some(read_string(data, cursor)?);
+    }
+    if flags & META_GAME_VERSION != 0 {
+        metadata.game_version = Some(read_string(data, cursor)?);
+    }
+    if flags & META_MODS != 0 {
+        let count = read_leb128(cursor)? as usize;
+        for _ in 0..count {
+            metadata.mods.push(read_string(data, cursor)?);
+        }
+    }
+    if flags & META_ONLINE_ID != 0 {
+        metadata.online_id = Some(read_i64(data, cursor)?);
+    }
+    if flags & META_SCORE != 0 {
+        let score = read_leb128(cursor)?;
+        let max_combo = read_leb128(cursor)? as u32;
+        let perfect = read_u8(data, cursor)? != 0;
+        let count = read_leb128(cursor)? as usize;
+        let mut judgements = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name = read_string(data, cursor)?;
+            let count = read_leb128(cursor)? as u32;
+            judgements.push(JudgementCount { name, count });
+        }
+        metadata.score = Some(ScoreSummary {
+            score,
+            max_combo,
+            perfect,
+            judgements,
+        });
+    }
+    if flags & META_HEALTH != 0 {
+        let count = read_leb128(cursor)? as usize;
+        for _ in 0..count {
+            let time_us = read_i64(data, cursor)?;
+            let health = f64::from_bits(read_i64(data, cursor)? as u64);
+            metadata.health_graph.push(HealthPoint { time_us, health });
+        }
+    }
+
+    Ok(metadata)
+}
+
+/// Reads `len` raw bytes from data.
+pub(super) fn read_bytes<'a>(
+    data: &'a [u8],
+    cursor: &mut Cursor<&[u8]>,
+    len: usize,
+) -> LeptonResult<&'a [u8]> {
+    if cursor.position() + len as u64 > data.len() as u64 {
+        return Err(LeptonError::InvalidMagic);
+    }
+    let bytes = &data[cursor.position() as usize..cursor.position() as usize + len];
+    cursor.set_position(cursor.position() + len as u64);
+    Ok(bytes)
+}
+
+/// Reads all judgements from data.
+fn read_judgements(
+    data: &[u8],
+    cursor: &mut Cursor<&[u8]>,
+) -> LeptonResult<Vec<RecordedJudgement>> {
+    let count = read_leb128(cursor)? as usize;
+    let mut judgements = Vec::with_capacity(count);
+    for _ in 0..count {
+        let note_index = read_leb128(cursor)? as u32;
+        let judgement = read_string(data, cursor)?;
+        let offset_us = if read_u8(data, cursor)? != 0 {
+            Some(read_i64(data, cursor)?)
+        } else {
+            None
+        };
+        judgements.push(RecordedJudgement {
+            note_index,
+            judgement,
+            offset_us,
+        });
+    }
+    Ok(judgements)
+}
+
+/// Reads the v1 trailer (optional metadata block and start offset) into replay.
+pub(super) fn read_v1_trailer(
+    data: &[u8],
+    cursor: &mut Cursor<&[u8]>,
+    replay: &mut ReplayData,
+) -> LeptonResult<()> {
+    replay.metadata = read_metadata(data, cursor)?;
+    if cursor.position() < data.len() as u64 {
+        replay.start_offset_us = read_i64(data, cursor)?;
+    }
+    Ok(())
+}
+
+/// Reads the fixed v1 layout: rate, hash, inputs, then the optional
+/// metadata block and start offset.
+fn read_v1(data: &[u8], cursor: &mut Cursor<&[u8]>) -> LeptonResult<ReplayData> {
+    let rate = read_rate(data, cursor)?;
+    let hash = read_hash(data, cursor)?;
+    let inputs = read_inputs(data, cursor)?;
+    let mut replay = ReplayData::with_params(rate, hash, inputs);
+    replay.version = 1;
+    read_v1_trailer(data, cursor, &mut replay)?;
+    Ok(replay)
+}
+
+/// Reads the header section payload (rate, hash, start offset) into replay.
+pub(super) fn read_header(payload: &[u8], replay: &mut ReplayData) -> LeptonResult<()> {
+    let mut cursor = Cursor::new(payload);
+    replay.rate = read_rate(payload, &mut cursor)?;
+    replay.hash = read_hash(payload, &mut cursor)?;
+    replay.start_offset_us = read_i64(payload, &mut cursor)?;
+    Ok(())
+}
+
+/// Reads a v2 section other than the header and inputs into replay.
+///
+/// Sections with an unknown tag are skipped. Each section is read from its
+/// own payload, so bytes added at the end of a known section are ignored.
+pub(super) fn read_section(tag: u8, payload: &[u8], replay: &mut ReplayData) -> LeptonResult<()> {
+    let mut cursor = Cursor::new(payload);
+    match tag {
+        SECTION_METADATA => replay.metadata = read_metadata(payload, &mut cursor)?,
+        SECTION_JUDGEMENTS => replay.judgements = read_judgements(payload, &mut cursor)?,
+        SECTION_EXTENSION => {
+            let name = read_string(payload, &mut cursor)?;
+            let data = payload[cursor.position() as usize..].to_vec();
+            replay.extensions.insert(name, data);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Size of the checksum section: tag, length and CRC32C.
+pub(super) const CHECKSUM_SECTION_LEN: usize = 6;
+
+/// Checks the checksum section ending data, and returns data without it.
+pub(super) fn verify_checksum(data: &[u8]) -> LeptonResult<&[u8]> {
+    let Some(split) = data.len().checked_sub(CHECKSUM_SECTION_LEN) else {
+        return Err(LeptonError::ChecksumMismatch);
+    };
+    let (body, section) = data.split_at(split);
+    if section[..2] != [SECTION_CHECKSUM, 4] {
+        return Err(LeptonError::ChecksumMismatch);
+    }
+    let crc = u32::from_le_bytes([section[2], section[3], section[4], section[5]]);
+    if crc != crc32c::crc32c(body) {
+        return Err(LeptonError::ChecksumMismatch);
+    }
+    Ok(body)
+}
+
+/// Reads the v2 sections until the end of data.
+///
+/// The checksum is verified first, so truncated or corrupted files fail with
+/// `LeptonError::ChecksumMismatch`. Inputs may be split across several
+/// sections, which are concatenated.
+fn read_v2(data: &[u8], cursor: &mut Cursor<&[u8]>) -> LeptonResult<ReplayData> {
+    let data = verify_checksum(data)?;
+    let mut replay = ReplayData::new(Vec::new());
+    let mut has_header = false;
+
+    while cursor.position() < data.len() as u64 {
+        let tag = read_u8(data, cursor)?;
+        let len = read_leb128(cursor)? as usize;
+        let payload = read_bytes(data, cursor, len)?;
+
+        match tag {
+            SECTION_HEADER => {
+                read_header(payload, &mut replay)?;
+                has_header = true;
+            }
+            SECTION_INPUTS => {
+                let inputs = read_inputs(payload, &mut Cursor::new(payload))?;
+                replay.inputs.extend(inputs);
+            }
+            tag => read_section(tag, payload, &mut replay)?,
+        }
+    }
+
+    if !has_header {
+        return Err(missing_header());
+    }
+    Ok(replay)
+}
+
+/// Error returned for v2 files without a header section.
+pub(super) fn missing_header() -> LeptonError {
+    LeptonError::Custom("LEP file has no header section".into())
+}
+
+/// Decompressed file header of a v3 file.
+pub(super) struct FileHeader {
+    pub(super) version: u8,
+    pub(super) codec: u8,
+    /// CRC32C of the zstd dictionary, for `CODEC_ZSTD_DICTIONARY`.
+    pub(super) dictionary_id: Option<u32>,
+    /// Length of the header in the file.
+    pub(super) len: usize,
+}
+
+/// Reads the uncompressed header of a v3 file, or `None` for older files,
+/// which are compressed whole.
+pub(super) fn read_file_header(data: &[u8]) -> LeptonResult<Option<FileHeader>> {
+    if !data.starts_with(MAGIC) {
+        return Ok(None);
+    }
+    let (Some(&version), Some(&codec)) = (data.get(4), data.get(5)) else {
+        return Err(LeptonError::InvalidMagic);
+    };
+    let dictionary_id = match codec {
+        CODEC_NONE | CODEC_ZSTD => None,
+        CODEC_ZSTD_DICTIONARY => {
+            let id = data.get(6..10).ok_or(LeptonError::InvalidMagic)?;
+            Some(u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+        }
+        codec => {
+            return Err(LeptonError::Custom(format!(
+                "Unknown LEP compression codec: {}",
+                codec
+            )));
+        }
+    };
+    Ok(Some(FileHeader {
+        version,
+        codec,
+        dictionary_id,
+        len: if dictionary_id.is_some() { 10 } else { 6 },
+    }))
+}
+
+/// Checks that `dictionary` is the one a file was compressed with.
+pub(super) fn check_dictionary(
+    dictionary_id: Option<u32>,
+    dictionary: Option<&[u8]>,
+) -> LeptonResult<()> {
+    let Some(id) = dictionary_id else {
+        return Ok(());
+    };
+    match dictionary {
+        Some(dictionary) if crc32c::crc32c(dictionary) == id => Ok(()),
+        Some(_) => Err(LeptonError::Custom(format!(
+            "LEP file was compressed with another zstd dictionary ({:08x})",
+            id
+        ))),
+        None => Err(LeptonError::Custom(format!(
+            "LEP file needs the zstd dictionary {:08x}",
+            id
+        ))),
+    }
+}
+
+/// Decompresses a LEP file into its payload: magic, version, then the
+/// sections (or the v1 layout).
+///
+/// A v3 body zstd cannot decompress was truncated or corrupted, and fails with
+/// `LeptonError::ChecksumMismatch` like one failing its checksum.
+pub(super) fn unpack(data: &[u8], dictionary: Option<&[u8]>) -> LeptonResult<Vec<u8>> {
+    let Some(header) = read_file_header(data)? else {
+        return Ok(zstd::decode_all(data)?);
+    };
+    check_dictionary(header.dictionary_id, dictionary)?;
+
+    let mut payload = MAGIC.to_vec();
+    payload.push(header.version);
+    let body = &data[header.len..];
+    let corrupted = |_| LeptonError::ChecksumMismatch;
+    match (header.codec, dictionary) {
+        (CODEC_NONE, _) => payload.extend_from_slice(body),
+        (CODEC_ZSTD_DICTIONARY, Some(dictionary)) => {
+            zstd::stream::read::Decoder::with_dictionary(body, dictionary)?
+                .read_to_end(&mut payload)
+                .map_err(corrupted)?;
+        }
+        _ => payload.extend_from_slice(&zstd::decode_all(body).map_err(corrupted)?),
+    }
+    Ok(payload)
+}
+
+/// Decodes a decompressed payload.
+fn decode_payload(payload: &[u8]) -> LeptonResult<ReplayData> {
+    let mut cursor = Cursor::new(payload);
+    read_magic(payload, &mut cursor)?;
+    let version = read_version(payload, &mut cursor)?;
+    let mut replay = match version {
+        1 => read_v1(payload, &mut cursor)?,
+        2 | 3 => read_v2(payload, &mut cursor)?,
+        version => return Err(LeptonError::UnsupportedVersion(version)),
+    };
+    replay.version = version;
+    Ok(replay)
+}
+
 impl Decoder for LepDecoder {
     fn decode(data: &[u8]) -> LeptonResult<ReplayData> {
-        let raw_data = zstd::decode_all(data)?;
-        let mut cursor = Cursor::new(raw_data.as_slice());
-
-        read_magic(&raw_data, &mut cursor)?;
-        let version = read_version(&raw_data, &mut cursor)?;
-        let rate = read_rate(&raw_data, &mut cursor)?;
-        let hash = read_hash(&raw_data, &mut cursor)?;
-        let inputs = read_inputs(&raw_data, &mut cursor)?;
+        decode_payload(&unpack(data, None)?)
+    }
+}
 
-        Ok(ReplayData {
-            version,
-            rate,
-            hash,
-            inputs,
-        })
+impl LepDecoder {
+    /// Decodes a file compressed with a zstd dictionary
+    /// (`LepCompression::ZstdDictionary`). Files without one decode as usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file was compressed with another dictionary or
+    /// is invalid.
+    pub fn decode_with_dictionary(data: &[u8], dictionary: &[u8]) -> LeptonResult<ReplayData> {
+        decode_payload(&unpack(data, Some(dictionary))?)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::codec::formats::lep::encoder::LepEncoder;
+    use crate::codec::formats::lep::encoder::{LepEncoder, LepEncoderOptions, write_checksum};
     use crate::codec::traits::Encoder;
 
-    // All tests use roundtrip encoding/decoding since compression is mandatory
+    // Tests editing section bytes encode without compression
 
     #[test]
     fn test_decode_empty_replay() {
@@ -124,7 +480,7 @@ mod tests {
         let encoded = LepEncoder::encode(&replay).unwrap();
         let decoded = LepDecoder::decode(&encoded).unwrap();
 
-        assert_eq!(decoded.version, 1);
+        assert_eq!(decoded.version, 3);
         assert_eq!(decoded.rate, 1.0);
         assert_eq!(decoded.hash, None);
         assert_eq!(decoded.inputs.len(), 0);
@@ -158,10 +514,10 @@ mod tests {
 
         // Verify packed data
         assert_eq!(decoded.inputs[0].column(), 0);
-        assert_eq!(decoded.inputs[0].is_press(), true);
-        assert_eq!(decoded.inputs[0].is_auto(), false);
+        assert!(decoded.inputs[0].is_press());
+        assert!(!decoded.inputs[0].is_auto());
 
-        assert_eq!(decoded.inputs[2].is_auto(), true);
+        assert!(decoded.inputs[2].is_auto());
     }
 
     #[test]
@@ -195,4 +551,243 @@ mod tests {
 
         assert_eq!(decoded, replay);
     }
+
+    #[test]
+    fn test_roundtrip_metadata() {
+        let metadata = ReplayMetadata {
+            player: Some("peppy".to_string()),
+            timestamp_ns: Some(1_700_000_000_123_456_700),
+            game: Some("osu!".to_string()),
+            game_version: Some("20240101".to_string()),
+            mods: vec!["HD".to_string(), "DT".to_string()],
+            online_id: Some(-1),
+            score: Some(ScoreSummary {
+                score: 987_654,
+                max_combo: 1234,
+                perfect: false,
+                judgements: vec![
+                    JudgementCount::new("300", 900),
+                    JudgementCount::new("Miss", 3),
+                ],
+            }),
+            health_graph: vec![
+                HealthPoint {
+                    time_us: 2_000_000,
+                    health: 1.0,
+                },
+                HealthPoint {
+                    time_us: 4_000_000,
+                    health: 0.875,
+                },
+            ],
+        };
+        let replay = ReplayData::with_params(1.5, None, vec![ReplayInput::new(0, 0, true, false)])
+            .with_metadata(metadata);
+
+        let encoded = LepEncoder::encode(&replay).unwrap();
+        let decoded = LepDecoder::decode(&encoded).unwrap();
+        assert_eq!(decoded, replay);
+
+        // Partial metadata
+        let replay = ReplayData::new(vec![]).with_metadata(ReplayMetadata {
+            game: Some("Etterna".to_string()),
+            ..Default::default()
+        });
+        let decoded = LepDecoder::decode(&LepEncoder::encode(&replay).unwrap()).unwrap();
+        assert_eq!(decoded, replay);
+    }
+
+    #[test]
+    fn test_roundtrip_start_offset() {
+        // Without metadata, the empty metadata block comes before the offset
+        let replay = ReplayData::new(vec![ReplayInput::new(0, 1, true, false)])
+            .with_start_offset(-1_500_000);
+        let decoded = LepDecoder::decode(&LepEncoder::encode(&replay).unwrap()).unwrap();
+        assert_eq!(decoded, replay);
+
+        let replay = replay.with_metadata(ReplayMetadata {
+            player: Some("peppy".to_string()),
+            ..Default::default()
+        });
+        let decoded = LepDecoder::decode(&LepEncoder::encode(&replay).unwrap()).unwrap();
+        assert_eq!(decoded, replay);
+    }
+
+    #[test]
+    fn test_roundtrip_judgements_and_extensions() {
+        let mut replay = ReplayData::new(vec![ReplayInput::new(1000, 0, true, false)])
+            .with_judgements(vec![
+                RecordedJudgement::new(0, "300", Some(-12_000)),
+                RecordedJudgement::new(1, "Miss", None),
+            ]);
+        replay
+            .extensions
+            .insert("app.notes".to_string(), b"good run".to_vec());
+        replay.extensions.insert("empty".to_string(), Vec::new());
+
+        let decoded = LepDecoder::decode(&LepEncoder::encode(&replay).unwrap()).unwrap();
+        assert_eq!(decoded, replay);
+    }
+
+    #[test]
+    fn test_unknown_sections_are_skipped() {
+        let replay = ReplayData::with_params(
+            1.5,
+            Some("hash".to_string()),
+            vec![ReplayInput::new(1000, 3, true, false)],
+        );
+        let mut raw =
+            LepEncoder::encode_with_options(&replay, &LepEncoderOptions::uncompressed()).unwrap();
+        // Unknown section between the header and the inputs, and one before
+        // the checksum. The codec byte is not covered by the checksum.
+        raw.truncate(raw.len() - CHECKSUM_SECTION_LEN);
+        let unknown = [200, 3, 0xde, 0xad, 0xbe];
+        let header_len = 6 + 2 + raw[7] as usize;
+        raw.splice(header_len..header_len, unknown);
+        raw.extend_from_slice(&unknown);
+        let crc = crc32c::crc32c_append(crc32c::crc32c(&raw[..5]), &raw[6..]);
+        write_checksum(crc, &mut raw);
+
+        assert_eq!(LepDecoder::decode(&raw).unwrap(), replay);
+    }
+
+    #[test]
+    fn test_decode_v1() {
+        // v1 layout: magic, version, rate, hash, inputs, metadata, start offset
+        let mut raw = b"LEP\0".to_vec();
+        raw.push(1);
+        raw.extend_from_slice(&1.5f64.to_le_bytes());
+        raw.extend_from_slice(&[4, b'h', b'a', b's', b'h']);
+        raw.extend_from_slice(&[2, 0x80, 0x08, 0x10, 100, 0x00]);
+        raw.extend_from_slice(&[META_GAME, 4, b'o', b's', b'u', b'!']);
+        raw.extend_from_slice(&(-1000i64).to_le_bytes());
+
+        let data = zstd::encode_all(raw.as_slice(), 3).unwrap();
+        let decoded = LepDecoder::decode(&data).unwrap();
+        assert_eq!(decoded.version, 1);
+        assert_eq!(decoded.rate, 1.5);
+        assert_eq!(decoded.hash.as_deref(), Some("hash"));
+        assert_eq!(
+            decoded.inputs,
+            [
+                ReplayInput::new(1024, 0, true, false),
+                ReplayInput::new(100, 0, false, false)
+            ]
+        );
+        assert_eq!(decoded.metadata.game.as_deref(), Some("osu!"));
+        assert_eq!(decoded.start_offset_us, -1000);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let data = zstd::encode_all(&b"LEP\0\x09"[..], 3).unwrap();
+        assert!(matches!(
+            LepDecoder::decode(&data),
+            Err(LeptonError::UnsupportedVersion(9))
+        ));
+        assert!(matches!(
+            LepDecoder::decode(b"LEP\0\x09\x00"),
+            Err(LeptonError::UnsupportedVersion(9))
+        ));
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let replay = ReplayData::with_params(
+            1.0,
+            Some("hash".to_string()),
+            (0..100)
+                .map(|i| ReplayInput::new(i * 1000, (i % 4) as u8, i % 2 == 0, false))
+                .collect(),
+        );
+        let raw =
+            LepEncoder::encode_with_options(&replay, &LepEncoderOptions::uncompressed()).unwrap();
+        assert_eq!(LepDecoder::decode(&raw).unwrap(), replay);
+
+        // Bit flip in an input delta
+        let mut flipped = raw.clone();
+        flipped[41] ^= 0x04;
+        assert!(matches!(
+            LepDecoder::decode(&flipped),
+            Err(LeptonError::ChecksumMismatch)
+        ));
+
+        // Truncated, at any length
+        for len in [6, 20, raw.len() - CHECKSUM_SECTION_LEN, raw.len() - 1] {
+            assert!(matches!(
+                LepDecoder::decode(&raw[..len]),
+                Err(LeptonError::ChecksumMismatch)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_checksum_mismatch_compressed() {
+        let replay = ReplayData::with_params(
+            1.0,
+            Some("hash".to_string()),
+            (0..2000)
+                .map(|i| ReplayInput::new(i * 1000 % 7919, (i % 4) as u8, i % 2 == 0, false))
+                .collect(),
+        );
+        let data = LepEncoder::encode(&replay).unwrap();
+        let is_corrupted =
+            |data: &[u8]| matches!(LepDecoder::decode(data), Err(LeptonError::ChecksumMismatch));
+
+        // Truncated zstd frame
+        for len in [7, data.len() / 2, data.len() - 1] {
+            assert!(is_corrupted(&data[..len]), "truncated to {}", len);
+        }
+
+        // Bit flips in the zstd frame, and in the codec byte, which the
+        // checksum does not cover but which breaks decompression
+        for at in [5, 20, data.len() / 2, data.len() - 3] {
+            let mut flipped = data.clone();
+            flipped[at] ^= 0x01;
+            assert!(is_corrupted(&flipped), "flipped at {}", at);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_compression_options() {
+        let replay = ReplayData::with_params(
+            1.0,
+            Some("hash".to_string()),
+            (0..100)
+                .map(|i| ReplayInput::new(i * 1000, (i % 4) as u8, i % 2 == 0, false))
+                .collect(),
+        );
+        for options in [
+            LepEncoderOptions::uncompressed(),
+            LepEncoderOptions::zstd(19),
+        ] {
+            let data = LepEncoder::encode_with_options(&replay, &options).unwrap();
+            assert_eq!(LepDecoder::decode(&data).unwrap(), replay);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_dictionary() {
+        let replay = ReplayData::with_params(
+            1.0,
+            Some("hash".to_string()),
+            vec![ReplayInput::new(1000, 0, true, false)],
+        );
+        let dictionary = b"LEP replay dictionary".repeat(16);
+        let options = LepEncoderOptions::zstd_dictionary(3, dictionary.clone());
+        let data = LepEncoder::encode_with_options(&replay, &options).unwrap();
+
+        assert_eq!(
+            LepDecoder::decode_with_dictionary(&data, &dictionary).unwrap(),
+            replay
+        );
+        assert!(matches!(
+            LepDecoder::decode(&data),
+            Err(LeptonError::Custom(_))
+        ));
+        assert!(matches!(
+            LepDecoder::decode_with_dictionary(&data, b"another dictionary"),
+            Err(LeptonError::Custom(_))
+        ));
+    }
 }
@@ -1,16 +1,41 @@
 //! LEP format encoder.
 //!
-//! Encodes `ReplayData` into the compact LEP binary format with:
+//! Encodes `ReplayData` into the LEP v3 binary format:
 //! - Magic bytes: `"LEP\0"`
 //! - Version: 1 byte
-//! - Rate: f64 (8 bytes, little-endian)
-//! - Hash: LEB128 length + UTF-8 string
-//! - Inputs: LEB128 count + (LEB128 delta + packed byte) per input
-//! - zstd compression (mandatory)
+//! - Compression codec: 1 byte (none, zstd, or zstd with a dictionary),
+//!   followed by the dictionary ID (CRC32C of the dictionary, u32
+//!   little-endian) for the latter
+//! - Sections, compressed with the codec, each a tag byte + LEB128 payload
+//!   length + payload:
+//!   - Header: rate (f64 little-endian), hash (LEB128 length + UTF-8, empty
+//!     when absent), start offset (i64 little-endian)
+//!   - Metadata (only when not empty): presence flags byte, then each present
+//!     field. Strings are LEB128 length + UTF-8, integers are LEB128
+//!     (unsigned) or 8 bytes little-endian (signed).
+//!   - Inputs: LEB128 count + (LEB128 delta + packed byte) per input
+//!   - Judgements (only when not empty): LEB128 count + (LEB128 note index,
+//!     judgement string, offset flag byte + i64 offset when hit) per note
+//!   - Extension (one per extension): name string + data bytes
+//!   - Signature (optional, see `signature`): key ID string + Ed25519
+//!     signature, right before the checksum
+//!   - Checksum (always last): CRC32C (u32 little-endian) of the magic,
+//!     version and every section before it, uncompressed
+//!
+//! Readers skip sections with an unknown tag, so new sections can be added
+//! without breaking them. Checksums and signatures cover the uncompressed
+//! sections, so they do not depend on the codec: a file can be recompressed
+//! without re-signing it. The codec byte and dictionary ID are still checked.
+//! An unknown codec is rejected. A wrong codec, or a truncated or corrupted
+//! body, fails to decompress or to match the checksum, and both are reported
+//! as `LeptonError::ChecksumMismatch`. The dictionary ID must match the
+//! dictionary given to the decoder.
+
+use std::io::Write;
 
 use crate::codec::traits::Encoder;
 use crate::error::LeptonResult;
-use crate::model::ReplayData;
+use crate::model::{RecordedJudgement, ReplayData, ReplayInput, ReplayMetadata};
 
 use super::leb128_utils::write_leb128;
 
@@ -18,67 +43,328 @@ use super::leb128_utils::write_leb128;
 pub struct LepEncoder;
 
 /// Magic bytes for LEP format: "LEP\0"
-const MAGIC: &[u8; 4] = b"LEP\0";
+pub(crate) const MAGIC: &[u8; 4] = b"LEP\0";
 
 /// Current LEP format version
-const VERSION: u8 = 1;
+pub(crate) const VERSION: u8 = 3;
+
+/// Compression codecs of the LEP v3 header.
+pub(crate) const CODEC_NONE: u8 = 0;
+pub(crate) const CODEC_ZSTD: u8 = 1;
+pub(crate) const CODEC_ZSTD_DICTIONARY: u8 = 2;
+
+/// Default zstd compression level.
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// Compression of the LEP sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LepCompression {
+    /// Uncompressed sections.
+    None,
+
+    /// zstd at the given level.
+    Zstd { level: i32 },
+
+    /// zstd at the given level with a trained dictionary (see
+    /// `zstd::dict::from_samples`). Decoding needs the same dictionary.
+    ZstdDictionary { level: i32, dictionary: Vec<u8> },
+}
+
+/// Options of `LepEncoder::encode_with_options`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LepEncoderOptions {
+    pub compression: LepCompression,
+}
 
-/// Writes the LEP header (magic, version, rate) to buffer.
+impl Default for LepEncoderOptions {
+    fn default() -> Self {
+        Self::zstd(DEFAULT_ZSTD_LEVEL)
+    }
+}
+
+impl LepEncoderOptions {
+    /// Uncompressed output.
+    #[must_use]
+    pub fn uncompressed() -> Self {
+        Self {
+            compression: LepCompression::None,
+        }
+    }
+
+    /// zstd compression at `level`.
+    #[must_use]
+    pub fn zstd(level: i32) -> Self {
+        Self {
+            compression: LepCompression::Zstd { level },
+        }
+    }
+
+    /// zstd compression at `level` with a trained dictionary.
+    #[must_use]
+    pub fn zstd_dictionary(level: i32, dictionary: Vec<u8>) -> Self {
+        Self {
+            compression: LepCompression::ZstdDictionary { level, dictionary },
+        }
+    }
+}
+
+/// Section tags of the LEP v2 container.
+pub(crate) const SECTION_HEADER: u8 = 1;
+pub(crate) const SECTION_METADATA: u8 = 2;
+pub(crate) const SECTION_INPUTS: u8 = 3;
+pub(crate) const SECTION_JUDGEMENTS: u8 = 4;
+pub(crate) const SECTION_EXTENSION: u8 = 5;
+pub(crate) const SECTION_CHECKSUM: u8 = 6;
+pub(crate) const SECTION_SIGNATURE: u8 = 7;
+
+/// Presence flags of the metadata block.
+pub(crate) const META_PLAYER: u8 = 1 << 0;
+pub(crate) const META_TIMESTAMP: u8 = 1 << 1;
+pub(crate) const META_GAME: u8 = 1 << 2;
+pub(crate) const META_GAME_VERSION: u8 = 1 << 3;
+pub(crate) const META_MODS: u8 = 1 << 4;
+pub(crate) const META_ONLINE_ID: u8 = 1 << 5;
+pub(crate) const META_SCORE: u8 = 1 << 6;
+pub(crate) const META_HEALTH: u8 = 1 << 7;
+
+/// Writes a section (tag + LEB128 length + payload) to buffer.
+pub(super) fn write_section(tag: u8, payload: &[u8], buffer: &mut Vec<u8>) {
+    buffer.push(tag);
+    write_leb128(payload.len() as u64, buffer);
+    buffer.extend_from_slice(payload);
+}
+
+/// Writes the header section payload (rate, hash, start offset) to buffer.
 fn write_header(replay: &ReplayData, buffer: &mut Vec<u8>) {
+    buffer.extend_from_slice(&replay.rate.to_le_bytes());
+    write_string(replay.hash.as_deref().unwrap_or_default(), buffer);
+    buffer.extend_from_slice(&replay.start_offset_us.to_le_bytes());
+}
+
+/// Writes inputs (count + delta/packed per input) to buffer.
+pub(super) fn write_inputs(inputs: &[ReplayInput], buffer: &mut Vec<u8>) {
+    write_leb128(inputs.len() as u64, buffer);
+    for input in inputs {
+        write_leb128(input.delta_us, buffer);
+        buffer.push(input.packed);
+    }
+}
+
+/// Writes a string (LEB128 length + UTF-8 bytes) to buffer.
+pub(super) fn write_string(value: &str, buffer: &mut Vec<u8>) {
+    write_leb128(value.len() as u64, buffer);
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+/// Writes the metadata block (presence flags + present fields) to buffer.
+fn write_metadata(metadata: &ReplayMetadata, buffer: &mut Vec<u8>) {
+    let mut flags = 0;
+    for (present, flag) in [
+        (metadata.player.is_some(), META_PLAYER),
+        (metadata.timestamp_ns.is_some(), META_TIMESTAMP),
+        (metadata.game.is_some(), META_GAME),
+        (metadata.game_version.is_some(), META_GAME_VERSION),
+        (!metadata.mods.is_empty(), META_MODS),
+        (metadata.online_id.is_some(), META_ONLINE_ID),
+        (metadata.score.is_some(), META_SCORE),
+        (!metadata.health_graph.is_empty(), META_HEALTH),
+    ] {
+        if present {
+            flags |= flag;
+        }
+    }
+    buffer.push(flags);
+
+    if let Some(player) = &metadata.player {
+        write_string(player, buffer);
+    }
+    if let Some(timestamp_ns) = metadata.timestamp_ns {
+        buffer.extend_from_slice(&timestamp_ns.to_le_bytes());
+    }
+    if let Some(game) = &metadata.game {
+        write_string(game, buffer);
+    }
+    if let Some(game_version) = &metadata.game_version {
+        write_string(game_version, buffer);
+    }
+    if !metadata.mods.is_empty() {
+        write_leb128(metadata.mods.len() as u64, buffer);
+        for acronym in &metadata.mods {
+            write_string(acronym, buffer);
+        }
+    }
+    if let Some(online_id) = metadata.online_id {
+        buffer.extend_from_slice(&online_id.to_le_bytes());
+    }
+    if let Some(score) = &metadata.score {
+        write_leb128(score.score, buffer);
+        write_leb128(score.max_combo as u64, buffer);
+        buffer.push(score.perfect as u8);
+        write_leb128(score.judgements.len() as u64, buffer);
+        for judgement in &score.judgements {
+            write_string(&judgement.name, buffer);
+            write_leb128(judgement.count as u64, buffer);
+        }
+    }
+    if !metadata.health_graph.is_empty() {
+        write_leb128(metadata.health_graph.len() as u64, buffer);
+        for point in &metadata.health_graph {
+            buffer.extend_from_slice(&point.time_us.to_le_bytes());
+            buffer.extend_from_slice(&point.health.to_le_bytes());
+        }
+    }
+}
+
+/// Writes all judgements (count + note index/judgement/offset per note) to buffer.
+fn write_judgements(judgements: &[RecordedJudgement], buffer: &mut Vec<u8>) {
+    write_leb128(judgements.len() as u64, buffer);
+    for judgement in judgements {
+        write_leb128(judgement.note_index as u64, buffer);
+        write_string(&judgement.judgement, buffer);
+        match judgement.offset_us {
+            Some(offset_us) => {
+                buffer.push(1);
+                buffer.extend_from_slice(&offset_us.to_le_bytes());
+            }
+            None => buffer.push(0),
+        }
+    }
+}
+
+/// Writes the uncompressed file header: magic, version, codec and
+/// dictionary ID.
+pub(super) fn write_file_header(compression: &LepCompression, buffer: &mut Vec<u8>) {
     buffer.extend_from_slice(MAGIC);
     buffer.push(VERSION);
-    buffer.extend_from_slice(&replay.rate.to_le_bytes());
+    match compression {
+        LepCompression::None => buffer.push(CODEC_NONE),
+        LepCompression::Zstd { .. } => buffer.push(CODEC_ZSTD),
+        LepCompression::ZstdDictionary { dictionary, .. } => {
+            buffer.push(CODEC_ZSTD_DICTIONARY);
+            buffer.extend_from_slice(&crc32c::crc32c(dictionary).to_le_bytes());
+        }
+    }
 }
 
-/// Writes the hash (LEB128 length + UTF-8 bytes) to buffer.
-fn write_hash(hash: &Option<String>, buffer: &mut Vec<u8>) {
-    if let Some(hash) = hash {
-        let hash_bytes = hash.as_bytes();
-        write_leb128(hash_bytes.len() as u64, buffer);
-        buffer.extend_from_slice(hash_bytes);
-    } else {
-        write_leb128(0, buffer);
+/// Compresses the sections of a payload (magic, version, sections) into a
+/// LEP file.
+pub(super) fn pack(payload: &[u8], compression: &LepCompression) -> LeptonResult<Vec<u8>> {
+    let mut data = Vec::new();
+    write_file_header(compression, &mut data);
+    let sections = &payload[MAGIC.len() + 1..];
+    match compression {
+        LepCompression::None => data.extend_from_slice(sections),
+        LepCompression::Zstd { level } => {
+            data.extend_from_slice(&zstd::encode_all(sections, *level)?);
+        }
+        LepCompression::ZstdDictionary { level, dictionary } => {
+            let mut encoder =
+                zstd::stream::write::Encoder::with_dictionary(data, *level, dictionary)?;
+            encoder.write_all(sections)?;
+            data = encoder.finish()?;
+        }
     }
+    Ok(data)
 }
 
-/// Writes all inputs (count + delta/packed per input) to buffer.
-fn write_inputs(replay: &ReplayData, buffer: &mut Vec<u8>) {
-    write_leb128(replay.inputs.len() as u64, buffer);
-    for input in &replay.inputs {
-        write_leb128(input.delta_us, buffer);
-        buffer.push(input.packed);
+/// Writes the sections coming before the inputs: header and metadata.
+pub(super) fn write_preamble(replay: &ReplayData, buffer: &mut Vec<u8>) {
+    let mut payload = Vec::new();
+    write_header(replay, &mut payload);
+    write_section(SECTION_HEADER, &payload, buffer);
+
+    if !replay.metadata.is_empty() {
+        payload.clear();
+        write_metadata(&replay.metadata, &mut payload);
+        write_section(SECTION_METADATA, &payload, buffer);
     }
 }
 
+/// Writes the sections coming after the inputs: judgements and extensions.
+pub(super) fn write_trailer(replay: &ReplayData, buffer: &mut Vec<u8>) {
+    let mut payload = Vec::new();
+    if !replay.judgements.is_empty() {
+        write_judgements(&replay.judgements, &mut payload);
+        write_section(SECTION_JUDGEMENTS, &payload, buffer);
+    }
+
+    for (name, data) in &replay.extensions {
+        payload.clear();
+        write_string(name, &mut payload);
+        payload.extend_from_slice(data);
+        write_section(SECTION_EXTENSION, &payload, buffer);
+    }
+}
+
+/// Writes the checksum section for the `crc` of every byte written before it.
+pub(super) fn write_checksum(crc: u32, buffer: &mut Vec<u8>) {
+    write_section(SECTION_CHECKSUM, &crc.to_le_bytes(), buffer);
+}
+
 impl Encoder for LepEncoder {
     fn encode(replay: &ReplayData) -> LeptonResult<Vec<u8>> {
-        let mut buffer = Vec::new();
+        Self::encode_with_options(replay, &LepEncoderOptions::default())
+    }
+}
+
+impl LepEncoder {
+    /// Encodes a replay with the given compression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if compression fails (e.g. invalid level or dictionary).
+    pub fn encode_with_options(
+        replay: &ReplayData,
+        options: &LepEncoderOptions,
+    ) -> LeptonResult<Vec<u8>> {
+        let mut buffer = MAGIC.to_vec();
+        buffer.push(VERSION);
+        write_preamble(replay, &mut buffer);
+
+        let mut payload = Vec::new();
+        write_inputs(&replay.inputs, &mut payload);
+        write_section(SECTION_INPUTS, &payload, &mut buffer);
 
-        write_header(replay, &mut buffer);
-        write_hash(&replay.hash, &mut buffer);
-        write_inputs(replay, &mut buffer);
+        write_trailer(replay, &mut buffer);
+        write_checksum(crc32c::crc32c(&buffer), &mut buffer);
 
-        let compressed = zstd::encode_all(buffer.as_slice(), 3)?;
-        Ok(compressed)
+        pack(&buffer, &options.compression)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::ReplayInput;
-
-    // Note: These tests verify that encoding works, but cannot check raw binary format
-    // since compression is mandatory. Use roundtrip decoder tests to verify correctness.
 
     #[test]
     fn test_encode_empty_replay() {
         let replay = ReplayData::new(vec![]);
         let data = LepEncoder::encode(&replay).unwrap();
+        assert_eq!(&data[..6], b"LEP\0\x03\x01");
+    }
 
-        // Data should be compressed, so we can't check magic bytes directly
-        // Just verify it encodes without error
-        assert!(!data.is_empty());
+    #[test]
+    fn test_encode_uncompressed_layout() {
+        let replay = ReplayData::new(vec![ReplayInput::new(1000, 0, true, false)]);
+        let data =
+            LepEncoder::encode_with_options(&replay, &LepEncoderOptions::uncompressed()).unwrap();
+
+        assert_eq!(&data[..4], MAGIC);
+        assert_eq!(data[4], VERSION);
+        assert_eq!(data[5], CODEC_NONE);
+        assert_eq!(data[6], SECTION_HEADER);
+        assert_eq!(data[data.len() - 6], SECTION_CHECKSUM);
+    }
+
+    #[test]
+    fn test_encode_dictionary_header() {
+        let dictionary = b"dictionary".repeat(8);
+        let options = LepEncoderOptions::zstd_dictionary(3, dictionary.clone());
+        let data = LepEncoder::encode_with_options(&ReplayData::new(vec![]), &options).unwrap();
+
+        // The dictionary ID follows the codec, uncompressed
+        assert_eq!(data[5], CODEC_ZSTD_DICTIONARY);
+        assert_eq!(data[6..10], crc32c::crc32c(&dictionary).to_le_bytes());
     }
 
     #[test]
@@ -7,7 +7,7 @@
 //! - Hash string length
 //! - Input count
 //! - Delta microseconds between inputs
-use std::io::Cursor;
+use std::io::Read;
 
 use crate::error::{LeptonError, LeptonResult};
 
@@ -33,11 +33,11 @@ pub fn write_leb128(value: u64, buffer: &mut Vec<u8>) {
     leb128::write::unsigned(buffer, value).expect("Writing to Vec should never fail");
 }
 
-/// Reads a u64 value in LEB128 format from a cursor.
+/// Reads a u64 value in LEB128 format from a reader.
 ///
 /// # Arguments
 ///
-/// * `cursor` - The cursor to read from
+/// * `reader` - The reader (cursor or stream) to read from
 ///
 /// # Returns
 ///
@@ -46,12 +46,14 @@ pub fn write_leb128(value: u64, buffer: &mut Vec<u8>) {
 /// # Errors
 ///
 /// Returns `LeptonError::Leb128Read` if the data is invalid or truncated.
-pub fn read_leb128(cursor: &mut Cursor<&[u8]>) -> LeptonResult<u64> {
-    leb128::read::unsigned(cursor).map_err(LeptonError::Leb128Read)
+pub fn read_leb128<R: Read>(reader: &mut R) -> LeptonResult<u64> {
+    leb128::read::unsigned(reader).map_err(LeptonError::Leb128Read)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+
     use super::*;
 
     #[test]
@@ -1,6 +1,13 @@
 pub mod decoder;
 pub mod encoder;
 mod leb128_utils;
+pub mod signature;
+pub mod stream;
 
 pub use decoder::LepDecoder;
-pub use encoder::LepEncoder;
+pub use encoder::{LepCompression, LepEncoder, LepEncoderOptions};
+pub use signature::{
+    sign, sign_with_dictionary, signature_key_id, signature_key_id_with_dictionary, verify,
+    verify_with_dictionary,
+};
+pub use stream::{LepStreamDecoder, LepStreamEncoder};
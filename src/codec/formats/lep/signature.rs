@@ -0,0 +1,359 @@
+//! Ed25519 signatures of LEP files.
+//!
+//! A signed file has a signature section right before the checksum. Its
+//! payload is the key ID (LEB128 length + UTF-8) and the 64-byte Ed25519
+//! signature of every uncompressed byte before the section followed by the
+//! key ID, encoded as in the section so the boundary between the two is
+//! unambiguous. Neither the replay nor the key ID can change without breaking
+//! the signature.
+//!
+//! Key management is left to the caller: keys are raw 32-byte Ed25519 keys.
+//!
+//! Signatures cover the uncompressed sections, so they do not depend on the
+//! codec. Files compressed with a zstd dictionary need the `_with_dictionary`
+//! variants.
+
+use std::io::Cursor;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+
+use crate::error::{LeptonError, LeptonResult};
+
+use super::decoder::{read_bytes, read_file_header, read_string, read_u8, unpack, verify_checksum};
+use super::encoder::{
+    CODEC_NONE, CODEC_ZSTD_DICTIONARY, DEFAULT_ZSTD_LEVEL, LepCompression, MAGIC,
+    SECTION_SIGNATURE, VERSION, pack, write_checksum, write_section, write_string,
+};
+use super::leb128_utils::read_leb128;
+
+/// Decompresses a v2 or v3 LEP file and returns its payload without the
+/// checksum.
+fn read_payload(data: &[u8], dictionary: Option<&[u8]>) -> LeptonResult<Vec<u8>> {
+    let mut payload = unpack(data, dictionary)?;
+    if payload.len() < 5 || &payload[..4] != MAGIC {
+        return Err(LeptonError::InvalidMagic);
+    }
+    if !matches!(payload[4], 2 | 3) {
+        return Err(LeptonError::UnsupportedVersion(payload[4]));
+    }
+    let len = verify_checksum(&payload)?.len();
+    payload.truncate(len);
+    Ok(payload)
+}
+
+/// Compression of an encoded file, to write it back the same way.
+///
+/// The zstd level is not stored in the file, so zstd files are written back
+/// at `DEFAULT_ZSTD_LEVEL`.
+fn compression_of(data: &[u8], dictionary: Option<&[u8]>) -> LeptonResult<LepCompression> {
+    let level = DEFAULT_ZSTD_LEVEL;
+    Ok(match read_file_header(data)? {
+        Some(header) if header.codec == CODEC_NONE => LepCompression::None,
+        Some(header) if header.codec == CODEC_ZSTD_DICTIONARY => LepCompression::ZstdDictionary {
+            level,
+            dictionary: dictionary.unwrap_or_default().to_vec(),
+        },
+        _ => LepCompression::Zstd { level },
+    })
+}
+
+/// Key ID and signature of a signature section.
+type SignatureSection = (String, Signature);
+
+/// Splits a payload into the bytes covered by the signature and the
+/// signature section, if any.
+fn split_signature(payload: &[u8]) -> LeptonResult<(&[u8], Option<SignatureSection>)> {
+    let mut cursor = Cursor::new(payload);
+    cursor.set_position(5);
+    while cursor.position() < payload.len() as u64 {
+        let start = cursor.position() as usize;
+        let tag = read_u8(payload, &mut cursor)?;
+        let len = read_leb128(&mut cursor)? as usize;
+        let section = read_bytes(payload, &mut cursor, len)?;
+        if tag != SECTION_SIGNATURE {
+            continue;
+        }
+
+        // Sections after the signature would not be covered by it
+        if cursor.position() != payload.len() as u64 {
+            return Err(LeptonError::InvalidSignature);
+        }
+        let mut section_cursor = Cursor::new(section);
+        let key_id = read_string(section, &mut section_cursor)?;
+        let signature = Signature::from_slice(&section[section_cursor.position() as usize..])
+            .map_err(|_| LeptonError::InvalidSignature)?;
+        return Ok((&payload[..start], Some((key_id, signature))));
+    }
+    Ok((payload, None))
+}
+
+/// Message signed for `key_id`: the signed bytes followed by the
+/// length-prefixed key ID.
+fn signed_message(signed: &[u8], key_id: &str) -> Vec<u8> {
+    let mut message = signed.to_vec();
+    write_string(key_id, &mut message);
+    message
+}
+
+/// Signs an encoded LEP file with an Ed25519 secret key.
+///
+/// `key_id` is stored with the signature so the verifier can pick the
+/// matching public key. An existing signature is replaced. The file keeps its
+/// codec, and v2 files are upgraded to v3. The zstd level is not recorded in
+/// the file: zstd files are recompressed at `DEFAULT_ZSTD_LEVEL`, whatever
+/// level they were encoded with.
+///
+/// # Errors
+///
+/// Returns an error if `data` is not a valid LEP v2 or v3 file.
+pub fn sign(data: &[u8], key_id: &str, secret_key: &[u8; 32]) -> LeptonResult<Vec<u8>> {
+    sign_payload(data, None, key_id, secret_key)
+}
+
+/// Signs a LEP file compressed with a zstd dictionary, like [`sign`].
+///
+/// # Errors
+///
+/// Returns an error if the file was compressed with another dictionary or
+/// is not a valid LEP v2 or v3 file.
+pub fn sign_with_dictionary(
+    data: &[u8],
+    dictionary: &[u8],
+    key_id: &str,
+    secret_key: &[u8; 32],
+) -> LeptonResult<Vec<u8>> {
+    sign_payload(data, Some(dictionary), key_id, secret_key)
+}
+
+fn sign_payload(
+    data: &[u8],
+    dictionary: Option<&[u8]>,
+    key_id: &str,
+    secret_key: &[u8; 32],
+) -> LeptonResult<Vec<u8>> {
+    let mut payload = read_payload(data, dictionary)?;
+    payload[4] = VERSION;
+    let (signed, _) = split_signature(&payload)?;
+    let signature = SigningKey::from_bytes(secret_key).sign(&signed_message(signed, key_id));
+
+    let mut section = Vec::new();
+    write_string(key_id, &mut section);
+    section.extend_from_slice(&signature.to_bytes());
+
+    let mut buffer = signed.to_vec();
+    write_section(SECTION_SIGNATURE, &section, &mut buffer);
+    write_checksum(crc32c::crc32c(&buffer), &mut buffer);
+
+    pack(&buffer, &compression_of(data, dictionary)?)
+}
+
+/// Returns the key ID of a signed LEP file, or `None` if it is not signed.
+///
+/// The signature is not verified: use it to look up the public key to pass
+/// to [`verify`].
+///
+/// # Errors
+///
+/// Returns an error if `data` is not a valid LEP v2 or v3 file, or was
+/// compressed with a zstd dictionary.
+pub fn signature_key_id(data: &[u8]) -> LeptonResult<Option<String>> {
+    key_id_of(data, None)
+}
+
+/// Returns the key ID of a LEP file compressed with a zstd dictionary, like
+/// [`signature_key_id`], to look up the public key to pass to
+/// [`verify_with_dictionary`].
+///
+/// # Errors
+///
+/// Returns an error if the file was compressed with another dictionary or
+/// is not a valid LEP v2 or v3 file.
+pub fn signature_key_id_with_dictionary(
+    data: &[u8],
+    dictionary: &[u8],
+) -> LeptonResult<Option<String>> {
+    key_id_of(data, Some(dictionary))
+}
+
+fn key_id_of(data: &[u8], dictionary: Option<&[u8]>) -> LeptonResult<Option<String>> {
+    let payload = read_payload(data, dictionary)?;
+    Ok(split_signature(&payload)?.1.map(|(key_id, _)| key_id))
+}
+
+/// Verifies the signature of a LEP file against an Ed25519 public key, and
+/// returns the key ID it was signed with.
+///
+/// # Errors
+///
+/// Returns `LeptonError::MissingSignature` if the file is not signed, and
+/// `LeptonError::InvalidSignature` if it was modified after signing or
+/// signed with another key.
+pub fn verify(data: &[u8], public_key: &[u8; 32]) -> LeptonResult<String> {
+    verify_payload(data, None, public_key)
+}
+
+/// Verifies a LEP file compressed with a zstd dictionary, like [`verify`].
+///
+/// # Errors
+///
+/// Returns the errors of [`verify`], or an error if the file was compressed
+/// with another dictionary.
+pub fn verify_with_dictionary(
+    data: &[u8],
+    dictionary: &[u8],
+    public_key: &[u8; 32],
+) -> LeptonResult<String> {
+    verify_payload(data, Some(dictionary), public_key)
+}
+
+fn verify_payload(
+    data: &[u8],
+    dictionary: Option<&[u8]>,
+    public_key: &[u8; 32],
+) -> LeptonResult<String> {
+    let payload = read_payload(data, dictionary)?;
+    let (signed, signature) = split_signature(&payload)?;
+    let (key_id, signature) = signature.ok_or(LeptonError::MissingSignature)?;
+
+    let public_key =
+        VerifyingKey::from_bytes(public_key).map_err(|_| LeptonError::InvalidSignature)?;
+    public_key
+        .verify_strict(&signed_message(signed, &key_id), &signature)
+        .map_err(|_| LeptonError::InvalidSignature)?;
+    Ok(key_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::formats::lep::{LepDecoder, LepEncoder, LepEncoderOptions};
+    use crate::codec::traits::{Decoder, Encoder};
+    use crate::model::{ReplayData, ReplayInput};
+
+    const SECRET_KEY: [u8; 32] = [7; 32];
+
+    fn public_key(secret_key: &[u8; 32]) -> [u8; 32] {
+        SigningKey::from_bytes(secret_key)
+            .verifying_key()
+            .to_bytes()
+    }
+
+    fn encoded_replay() -> (ReplayData, Vec<u8>) {
+        let replay = ReplayData::with_params(
+            1.0,
+            Some("hash".to_string()),
+            vec![
+                ReplayInput::new(1000, 0, true, false),
+                ReplayInput::new(80_000, 0, false, false),
+            ],
+        );
+        let data = LepEncoder::encode(&replay).unwrap();
+        (replay, data)
+    }
+
+    /// Re-encodes the payload of a signed file after `tamper`, with a valid checksum.
+    fn tampered(data: &[u8], tamper: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+        let mut payload = read_payload(data, None).unwrap();
+        tamper(&mut payload);
+        write_checksum(crc32c::crc32c(&payload), &mut payload);
+        pack(&payload, &LepCompression::Zstd { level: 3 }).unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (replay, data) = encoded_replay();
+        assert!(matches!(
+            verify(&data, &public_key(&SECRET_KEY)),
+            Err(LeptonError::MissingSignature)
+        ));
+        assert_eq!(signature_key_id(&data).unwrap(), None);
+
+        let signed = sign(&data, "client-2026", &SECRET_KEY).unwrap();
+        assert_eq!(
+            verify(&signed, &public_key(&SECRET_KEY)).unwrap(),
+            "client-2026"
+        );
+        assert_eq!(
+            signature_key_id(&signed).unwrap().as_deref(),
+            Some("client-2026")
+        );
+        // Signed files still decode
+        assert_eq!(LepDecoder::decode(&signed).unwrap(), replay);
+
+        // Signing again replaces the signature
+        let resigned = sign(&signed, "client-2027", &[9; 32]).unwrap();
+        assert_eq!(
+            verify(&resigned, &public_key(&[9; 32])).unwrap(),
+            "client-2027"
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_tampering() {
+        let (_, data) = encoded_replay();
+        let signed = sign(&data, "client", &SECRET_KEY).unwrap();
+        let key = public_key(&SECRET_KEY);
+
+        // Wrong key
+        assert!(matches!(
+            verify(&signed, &public_key(&[8; 32])),
+            Err(LeptonError::InvalidSignature)
+        ));
+
+        // Edited first input delta (after a 28-byte header and the inputs tag, length and count)
+        let edited = tampered(&signed, |payload| payload[31] ^= 0x01);
+        assert!(matches!(
+            verify(&edited, &key),
+            Err(LeptonError::InvalidSignature)
+        ));
+
+        // Edited key ID
+        let edited = tampered(&signed, |payload| {
+            let at = payload.len() - 64 - 1;
+            payload[at] = b'X';
+        });
+        assert!(matches!(
+            verify(&edited, &key),
+            Err(LeptonError::InvalidSignature)
+        ));
+
+        // Section appended after the signature
+        let edited = tampered(&signed, |payload| payload.extend_from_slice(&[200, 0]));
+        assert!(matches!(
+            verify(&edited, &key),
+            Err(LeptonError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_signed_message_key_id_boundary() {
+        // Moving bytes between the payload and the key ID changes the message
+        assert_ne!(signed_message(b"ab", "c"), signed_message(b"a", "bc"));
+        assert_eq!(signed_message(b"ab", "c"), b"ab\x01c");
+    }
+
+    #[test]
+    fn test_sign_and_verify_with_dictionary() {
+        let (replay, _) = encoded_replay();
+        let dictionary = b"LEP replay dictionary".repeat(16);
+        let options = LepEncoderOptions::zstd_dictionary(3, dictionary.clone());
+        let data = LepEncoder::encode_with_options(&replay, &options).unwrap();
+
+        let signed = sign_with_dictionary(&data, &dictionary, "client", &SECRET_KEY).unwrap();
+        assert_eq!(
+            signature_key_id_with_dictionary(&signed, &dictionary)
+                .unwrap()
+                .as_deref(),
+            Some("client")
+        );
+        assert_eq!(
+            verify_with_dictionary(&signed, &dictionary, &public_key(&SECRET_KEY)).unwrap(),
+            "client"
+        );
+        assert!(signature_key_id(&signed).is_err());
+        assert_eq!(
+            LepDecoder::decode_with_dictionary(&signed, &dictionary).unwrap(),
+            replay
+        );
+    }
+}
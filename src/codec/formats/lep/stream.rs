@@ -0,0 +1,755 @@
+//! Streaming LEP encoder and decoder.
+//!
+//! `LepStreamEncoder` takes inputs one at a time and `LepStreamDecoder`
+//! yields them through an iterator, both over `zstd::stream`. Only the
+//! header, metadata, judgements and extensions are kept in memory, so memory
+//! stays bounded whatever the number of inputs.
+//!
+//! The encoder writes inputs in sections of `INPUTS_PER_SECTION` inputs,
+//! which `LepDecoder` concatenates back.
+//!
+//! The checksum comes last, so the decoder can only report a corrupted file
+//! (`LeptonError::ChecksumMismatch`) once every input has been yielded. Data
+//! zstd cannot decompress is reported the same way as soon as it is read,
+//! while errors of the underlying reader stay `LeptonError::Io`.
+
+use std::io::{BufReader, Chain, Cursor, Read, Write};
+
+use crate::error::{LeptonError, LeptonResult};
+use crate::model::{ReplayData, ReplayInput};
+
+use super::decoder::{
+    CHECKSUM_SECTION_LEN, check_dictionary, missing_header, read_file_header, read_header,
+    read_section, read_v1_trailer,
+};
+use super::encoder::{
+    CODEC_NONE, CODEC_ZSTD_DICTIONARY, LepCompression, LepEncoderOptions, MAGIC, SECTION_CHECKSUM,
+    SECTION_HEADER, SECTION_INPUTS, VERSION, write_checksum, write_file_header, write_inputs,
+    write_preamble, write_section, write_trailer,
+};
+use super::leb128_utils::read_leb128;
+
+/// Number of inputs buffered before the encoder writes an inputs section.
+pub const INPUTS_PER_SECTION: usize = 4096;
+
+/// Streaming LEP encoder writing to any `Write`.
+///
+/// # Example
+///
+/// ```ignore
+/// let template = ReplayData::with_params(1.0, Some(hash), Vec::new());
+/// let mut encoder = LepStreamEncoder::new(File::create("replay.lep")?, &template)?;
+/// for input in inputs {
+///     encoder.push(input)?;
+/// }
+/// encoder.finish()?;
+/// ```
+pub struct LepStreamEncoder<W: Write> {
+    writer: StreamWriter<W>,
+    pending: Vec<ReplayInput>,
+    trailer: Vec<u8>,
+    /// CRC32C of every byte written so far.
+    crc: u32,
+}
+
+impl<W: Write> LepStreamEncoder<W> {
+    /// Starts a replay with the header, metadata, judgements and extensions of
+    /// `replay`. Its inputs, if any, are written first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compressor cannot be created or writing fails.
+    pub fn new(writer: W, replay: &ReplayData) -> LeptonResult<Self> {
+        Self::with_options(writer, replay, &LepEncoderOptions::default())
+    }
+
+    /// Starts a replay like `new`, with the given compression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compressor cannot be created or writing fails.
+    pub fn with_options(
+        mut writer: W,
+        replay: &ReplayData,
+        options: &LepEncoderOptions,
+    ) -> LeptonResult<Self> {
+        let mut header = Vec::new();
+        write_file_header(&options.compression, &mut header);
+        writer.write_all(&header)?;
+        let writer = match &options.compression {
+            LepCompression::None => StreamWriter::Raw(writer),
+            LepCompression::Zstd { level } => {
+                StreamWriter::Zstd(zstd::stream::write::Encoder::new(writer, *level)?)
+            }
+            LepCompression::ZstdDictionary { level, dictionary } => StreamWriter::Zstd(
+                zstd::stream::write::Encoder::with_dictionary(writer, *level, dictionary)?,
+            ),
+        };
+
+        let mut trailer = Vec::new();
+        write_trailer(replay, &mut trailer);
+
+        // The checksum covers the magic and version, not the codec
+        let mut encoder = Self {
+            writer,
+            pending: Vec::with_capacity(INPUTS_PER_SECTION),
+            trailer,
+            crc: crc32c::crc32c_append(crc32c::crc32c(MAGIC), &[VERSION]),
+        };
+        let mut buffer = Vec::new();
+        write_preamble(replay, &mut buffer);
+        encoder.write(&buffer)?;
+        for input in &replay.inputs {
+            encoder.push(input.clone())?;
+        }
+        Ok(encoder)
+    }
+
+    /// Appends an input to the replay.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing a full inputs section fails.
+    pub fn push(&mut self, input: ReplayInput) -> LeptonResult<()> {
+        self.pending.push(input);
+        if self.pending.len() >= INPUTS_PER_SECTION {
+            self.flush_inputs()?;
+        }
+        Ok(())
+    }
+
+    /// Writes bytes to the compressor, updating the checksum.
+    fn write(&mut self, bytes: &[u8]) -> LeptonResult<()> {
+        self.crc = crc32c::crc32c_append(self.crc, bytes);
+        self.writer.write_all(bytes)?;
+        Ok(())
+    }
+
+    /// Writes the pending inputs as a section.
+    fn flush_inputs(&mut self) -> LeptonResult<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let mut payload = Vec::new();
+        write_inputs(&self.pending, &mut payload);
+        let mut buffer = Vec::new();
+        write_section(SECTION_INPUTS, &payload, &mut buffer);
+        self.write(&buffer)?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Writes the remaining inputs and sections, ends the zstd frame and
+    /// returns the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn finish(mut self) -> LeptonResult<W> {
+        self.flush_inputs()?;
+        let mut trailer = std::mem::take(&mut self.trailer);
+        self.crc = crc32c::crc32c_append(self.crc, &trailer);
+        write_checksum(self.crc, &mut trailer);
+        self.writer.write_all(&trailer)?;
+        Ok(self.writer.finish()?)
+    }
+}
+
+/// Streaming LEP decoder reading from any `Read`.
+///
+/// The header is read on creation, inputs are then yielded by the iterator.
+/// Sections written after the inputs (judgements, extensions, and v1
+/// metadata) are only in `replay()` once the iterator is exhausted.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut decoder = LepStreamDecoder::new(File::open("replay.lep")?)?;
+/// for input in &mut decoder {
+///     let input = input?;
+/// }
+/// let replay = decoder.replay();
+/// ```
+pub struct LepStreamDecoder<R: Read> {
+    reader: ChecksumReader<StreamReader<R>>,
+    replay: ReplayData,
+    /// Inputs left in the current inputs section.
+    inputs_left: u64,
+    /// Payload bytes left in the current inputs section (v2 only).
+    section_left: u64,
+    done: bool,
+}
+
+impl<R: Read> LepStreamDecoder<R> {
+    /// Reads the file header, up to the first input.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not a valid LEP file.
+    pub fn new(reader: R) -> LeptonResult<Self> {
+        Self::open(reader, None)
+    }
+
+    /// Reads the file header of a file compressed with a zstd dictionary
+    /// (`LepCompression::ZstdDictionary`). Files without one decode as usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file was compressed with another dictionary or
+    /// is not a valid LEP file.
+    pub fn with_dictionary(reader: R, dictionary: &[u8]) -> LeptonResult<Self> {
+        Self::open(reader, Some(dictionary))
+    }
+
+    fn open(mut reader: R, dictionary: Option<&[u8]>) -> LeptonResult<Self> {
+        // Peek the v3 header (magic, version, codec, dictionary ID)
+        let mut peeked = Vec::new();
+        (&mut reader).take(6).read_to_end(&mut peeked)?;
+        if peeked.starts_with(MAGIC) && peeked.get(5) == Some(&CODEC_ZSTD_DICTIONARY) {
+            (&mut reader).take(4).read_to_end(&mut peeked)?;
+        }
+
+        let header = read_file_header(&peeked)?;
+        let (inner, crc) = match &header {
+            // Older files are compressed whole, magic and version included
+            None => {
+                let source = Cursor::new(peeked).chain(TrackedReader::new(reader));
+                (
+                    StreamReader::Zstd(zstd::stream::read::Decoder::new(source)?),
+                    0,
+                )
+            }
+            Some(header) => {
+                check_dictionary(header.dictionary_id, dictionary)?;
+                let source = Cursor::new(Vec::new()).chain(TrackedReader::new(reader));
+                let inner = match (header.codec, dictionary) {
+                    (CODEC_NONE, _) => StreamReader::Raw(source),
+                    (CODEC_ZSTD_DICTIONARY, Some(dictionary)) => {
+                        StreamReader::Zstd(zstd::stream::read::Decoder::with_dictionary(
+                            BufReader::new(source),
+                            dictionary,
+                        )?)
+                    }
+                    _ => StreamReader::Zstd(zstd::stream::read::Decoder::new(source)?),
+                };
+                let crc = crc32c::crc32c_append(crc32c::crc32c(MAGIC), &[header.version]);
+                (inner, crc)
+            }
+        };
+
+        let mut decoder = Self {
+            reader: ChecksumReader { inner, crc },
+            replay: ReplayData::new(Vec::new()),
+            inputs_left: 0,
+            section_left: 0,
+            done: false,
+        };
+        match decoder.read_start(header.map(|header| header.version)) {
+            Ok(()) => Ok(decoder),
+            Err(error) => Err(decoder.corrupted(error)),
+        }
+    }
+
+    /// Reads the version, if not in the file header, and the header up to
+    /// the first input.
+    fn read_start(&mut self, version: Option<u8>) -> LeptonResult<()> {
+        self.replay.version = match version {
+            Some(version) => version,
+            None => {
+                let mut magic = [0u8; 4];
+                self.reader
+                    .read_exact(&mut magic)
+                    .map_err(|_| LeptonError::InvalidMagic)?;
+                if &magic != MAGIC {
+                    return Err(LeptonError::InvalidMagic);
+                }
+                self.read_u8()?.ok_or(LeptonError::InvalidMagic)?
+            }
+        };
+        match self.replay.version {
+            1 => {
+                let mut rate = [0u8; 8];
+                self.reader.read_exact(&mut rate)?;
+                self.replay.rate = f64::from_le_bytes(rate);
+                let hash_len = read_leb128(&mut self.reader)?;
+                let hash = self.read_payload(hash_len)?;
+                self.replay.hash = Some(String::from_utf8(hash)?).filter(|h| !h.is_empty());
+                self.inputs_left = read_leb128(&mut self.reader)?;
+                self.section_left = u64::MAX;
+            }
+            2 | 3 => {
+                // The header comes first: stop at it, then read up to the inputs
+                match self.read_u8()? {
+                    Some(SECTION_HEADER) => {
+                        let len = read_leb128(&mut self.reader)?;
+                        let payload = self.read_payload(len)?;
+                        read_header(&payload, &mut self.replay)?;
+                    }
+                    _ => return Err(missing_header()),
+                }
+                self.next_inputs_section()?;
+            }
+            version => return Err(LeptonError::UnsupportedVersion(version)),
+        }
+        Ok(())
+    }
+
+    /// Returns the replay read so far, without inputs.
+    #[must_use]
+    pub fn replay(&self) -> &ReplayData {
+        &self.replay
+    }
+
+    /// Reads every remaining input and returns the complete replay.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is invalid.
+    pub fn into_replay(mut self) -> LeptonResult<ReplayData> {
+        let inputs = self.by_ref().collect::<LeptonResult<Vec<_>>>()?;
+        self.replay.inputs = inputs;
+        Ok(self.replay)
+    }
+
+    /// Reads a byte, or `None` at the end of the data.
+    fn read_u8(&mut self) -> LeptonResult<Option<u8>> {
+        let mut byte = [0u8; 1];
+        match self.reader.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    /// Reads `len` bytes.
+    fn read_payload(&mut self, len: u64) -> LeptonResult<Vec<u8>> {
+        let mut payload = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut payload)?;
+        if (payload.len() as u64) < len {
+            return Err(self.truncated());
+        }
+        Ok(payload)
+    }
+
+    /// Error for data ending early: v2 files end with a checksum, so a
+    /// truncated one is reported as corrupted.
+    fn truncated(&self) -> LeptonError {
+        if self.replay.version == 1 {
+            LeptonError::InvalidMagic
+        } else {
+            LeptonError::ChecksumMismatch
+        }
+    }
+
+    /// Reports errors caused by corrupted data as such: data zstd could not
+    /// decompress, or sections that cannot be read in a v2+ file whose
+    /// remaining bytes fail the checksum. Errors of the reader are kept.
+    fn corrupted(&mut self, error: LeptonError) -> LeptonError {
+        if self.reader.inner.source_failed() {
+            return error;
+        }
+        let io_error = match &error {
+            LeptonError::Io(error) => Some(error),
+            LeptonError::Leb128Read(leb128::read::Error::IoError(error)) => Some(error),
+            _ => None,
+        };
+        if io_error
+            .and_then(|error| error.get_ref())
+            .is_some_and(|inner| inner.is::<CorruptedBody>())
+        {
+            return self.truncated();
+        }
+        if self.replay.version == 1 || matches!(error, LeptonError::ChecksumMismatch) {
+            return error;
+        }
+        match self.rest_matches_checksum() {
+            Ok(false) => LeptonError::ChecksumMismatch,
+            Ok(true) => error,
+            Err(rest_error) => self.corrupted(rest_error),
+        }
+    }
+
+    /// Reads the rest of the data and checks it ends with the checksum of
+    /// every byte before it.
+    fn rest_matches_checksum(&mut self) -> LeptonResult<bool> {
+        let mut crc = self.reader.crc;
+        let mut tail = Vec::new();
+        let mut buffer = [0u8; 8192];
+        loop {
+            let read = self.reader.inner.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            tail.extend_from_slice(&buffer[..read]);
+            // Keep the last bytes, which may be the checksum section
+            let covered = tail.len().saturating_sub(CHECKSUM_SECTION_LEN);
+            crc = crc32c::crc32c_append(crc, &tail[..covered]);
+            tail.drain(..covered);
+        }
+        let mut expected = vec![SECTION_CHECKSUM, 4];
+        expected.extend_from_slice(&crc.to_le_bytes());
+        Ok(tail == expected)
+    }
+
+    /// Reads sections until the next inputs section, or the checksum ending
+    /// the data.
+    fn next_inputs_section(&mut self) -> LeptonResult<()> {
+        loop {
+            let crc = self.reader.crc;
+            let Some(tag) = self.read_u8()? else {
+                return Err(LeptonError::ChecksumMismatch);
+            };
+            let len = read_leb128(&mut self.reader)?;
+            if tag == SECTION_CHECKSUM {
+                let payload = self.read_payload(len)?;
+                if payload != crc.to_le_bytes() || self.read_u8()?.is_some() {
+                    return Err(LeptonError::ChecksumMismatch);
+                }
+                self.done = true;
+                return Ok(());
+            }
+            if tag == SECTION_INPUTS {
+                let mut section = (&mut self.reader).take(len);
+                self.inputs_left = read_leb128(&mut section)?;
+                self.section_left = section.limit();
+                return Ok(());
+            }
+            let payload = self.read_payload(len)?;
+            read_section(tag, &payload, &mut self.replay)?;
+        }
+    }
+
+    /// Finishes the current section once its inputs are read, and moves on.
+    fn end_inputs_section(&mut self) -> LeptonResult<()> {
+        if self.replay.version == 1 {
+            // The metadata block and start offset follow the inputs
+            let mut rest = Vec::new();
+            self.reader.read_to_end(&mut rest)?;
+            read_v1_trailer(&rest, &mut std::io::Cursor::new(&rest), &mut self.replay)?;
+            self.done = true;
+            return Ok(());
+        }
+        // Skip bytes added after the inputs by newer writers
+        self.read_payload(self.section_left)?;
+        self.next_inputs_section()
+    }
+
+    /// Reads the next input of the current section.
+    fn read_input(&mut self) -> LeptonResult<ReplayInput> {
+        let mut section = (&mut self.reader).take(self.section_left);
+        let delta_us = read_leb128(&mut section)?;
+        let mut packed = [0u8; 1];
+        if section.read_exact(&mut packed).is_err() {
+            return Err(self.truncated());
+        }
+        self.section_left = section.limit();
+        self.inputs_left -= 1;
+        Ok(ReplayInput {
+            delta_us,
+            packed: packed[0],
+        })
+    }
+}
+
+/// Compressed or raw output of the stream encoder.
+enum StreamWriter<W: Write> {
+    Raw(W),
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+}
+
+impl<W: Write> StreamWriter<W> {
+    /// Ends the zstd frame, if any, and returns the writer.
+    fn finish(self) -> std::io::Result<W> {
+        match self {
+            Self::Raw(writer) => Ok(writer),
+            Self::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for StreamWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Raw(writer) => writer.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Raw(writer) => writer.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Input of the stream decoder: the bytes peeked for the file header, then
+/// the rest of the reader.
+type Source<R> = Chain<Cursor<Vec<u8>>, TrackedReader<R>>;
+
+/// Reader remembering whether it failed, to tell its I/O errors apart from
+/// data zstd cannot decompress.
+struct TrackedReader<R: Read> {
+    inner: R,
+    failed: bool,
+}
+
+impl<R: Read> TrackedReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            failed: false,
+        }
+    }
+}
+
+impl<R: Read> Read for TrackedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf);
+        self.failed |= read.is_err();
+        read
+    }
+}
+
+/// Error of data zstd cannot decompress: a truncated or corrupted body.
+#[derive(Debug)]
+struct CorruptedBody;
+
+impl std::fmt::Display for CorruptedBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("corrupted zstd data")
+    }
+}
+
+impl std::error::Error for CorruptedBody {}
+
+/// Compressed or raw input of the stream decoder.
+enum StreamReader<R: Read> {
+    Raw(Source<R>),
+    Zstd(zstd::stream::read::Decoder<'static, BufReader<Source<R>>>),
+}
+
+impl<R: Read> StreamReader<R> {
+    /// Checks if the underlying reader failed.
+    fn source_failed(&self) -> bool {
+        let source = match self {
+            Self::Raw(source) => source,
+            Self::Zstd(decoder) => decoder.get_ref().get_ref(),
+        };
+        source.get_ref().1.failed
+    }
+}
+
+impl<R: Read> Read for StreamReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Raw(reader) => reader.read(buf),
+            Self::Zstd(decoder) => decoder.read(buf).map_err(|error| {
+                if self.source_failed() {
+                    error
+                } else {
+                    std::io::Error::other(CorruptedBody)
+                }
+            }),
+        }
+    }
+}
+
+/// Reader keeping the CRC32C of every byte read.
+struct ChecksumReader<R: Read> {
+    inner: R,
+    crc: u32,
+}
+
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.crc = crc32c::crc32c_append(self.crc, &buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<R: Read> Iterator for LepStreamDecoder<R> {
+    type Item = LeptonResult<ReplayInput>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done && self.inputs_left == 0 {
+            if let Err(e) = self.end_inputs_section() {
+                self.done = true;
+                return Some(Err(self.corrupted(e)));
+            }
+        }
+        if self.done {
+            return None;
+        }
+        let input = self.read_input();
+        if input.is_err() {
+            self.done = true;
+        }
+        Some(input.map_err(|e| self.corrupted(e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::formats::lep::{LepDecoder, LepEncoder};
+    use crate::codec::traits::{Decoder, Encoder};
+    use crate::model::{RecordedJudgement, ReplayMetadata};
+
+    fn long_replay(count: usize) -> ReplayData {
+        let inputs = (0..count)
+            .map(|i| ReplayInput::new((i % 997) as u64 * 100, (i % 7) as u8, i % 2 == 0, false))
+            .collect();
+        let mut replay = ReplayData::with_params(1.5, Some("hash".to_string()), inputs)
+            .with_start_offset(-2_000)
+            .with_metadata(ReplayMetadata {
+                player: Some("peppy".to_string()),
+                ..Default::default()
+            })
+            .with_judgements(vec![RecordedJudgement::new(0, "300", Some(1_000))]);
+        replay.extensions.insert("app".to_string(), vec![1, 2, 3]);
+        replay
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        let replay = long_replay(INPUTS_PER_SECTION * 2 + 17);
+
+        let template = ReplayData {
+            inputs: Vec::new(),
+            ..replay.clone()
+        };
+        let mut encoder = LepStreamEncoder::new(Vec::new(), &template).unwrap();
+        for input in &replay.inputs {
+            encoder.push(input.clone()).unwrap();
+        }
+        let data = encoder.finish().unwrap();
+
+        // Inputs split across sections decode with both decoders
+        assert_eq!(LepDecoder::decode(&data).unwrap(), replay);
+        let decoder = LepStreamDecoder::new(data.as_slice()).unwrap();
+        assert_eq!(decoder.replay().rate, 1.5);
+        assert_eq!(decoder.replay().metadata.player.as_deref(), Some("peppy"));
+        assert_eq!(decoder.into_replay().unwrap(), replay);
+    }
+
+    #[test]
+    fn test_stream_decode_lep_encoder_output() {
+        let replay = long_replay(100);
+        let data = LepEncoder::encode(&replay).unwrap();
+
+        let mut decoder = LepStreamDecoder::new(data.as_slice()).unwrap();
+        // Judgements and extensions come after the inputs
+        assert!(decoder.replay().judgements.is_empty());
+        let inputs: Vec<_> = decoder.by_ref().map(Result::unwrap).collect();
+        assert_eq!(inputs, replay.inputs);
+        assert_eq!(decoder.replay().judgements, replay.judgements);
+        assert_eq!(decoder.replay().extensions, replay.extensions);
+    }
+
+    #[test]
+    fn test_stream_decode_empty_and_corrupted() {
+        let replay = ReplayData::new(Vec::new());
+        let data = LepEncoder::encode(&replay).unwrap();
+        let decoder = LepStreamDecoder::new(data.as_slice()).unwrap();
+        assert_eq!(decoder.into_replay().unwrap(), replay);
+
+        let raw =
+            LepEncoder::encode_with_options(&long_replay(10), &LepEncoderOptions::uncompressed())
+                .unwrap();
+        let decoder = LepStreamDecoder::new(&raw[..raw.len() - 30]).unwrap();
+        assert!(decoder.into_replay().is_err());
+
+        // Checksum section dropped, or a flipped bit in the inputs
+        let decoder = LepStreamDecoder::new(&raw[..raw.len() - 6]).unwrap();
+        assert!(matches!(
+            decoder.into_replay(),
+            Err(LeptonError::ChecksumMismatch)
+        ));
+        let mut flipped = raw.clone();
+        flipped[41] ^= 0x04;
+        let decoder = LepStreamDecoder::new(flipped.as_slice()).unwrap();
+        assert!(matches!(
+            decoder.into_replay(),
+            Err(LeptonError::ChecksumMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_stream_decode_corrupted_compressed() {
+        let data = LepEncoder::encode(&long_replay(2000)).unwrap();
+        let is_corrupted = |data: &[u8]| {
+            let result = LepStreamDecoder::new(data).and_then(LepStreamDecoder::into_replay);
+            matches!(result, Err(LeptonError::ChecksumMismatch))
+        };
+
+        for len in [7, data.len() / 2, data.len() - 1] {
+            assert!(is_corrupted(&data[..len]), "truncated to {}", len);
+        }
+        for at in [5, 20, data.len() / 2, data.len() - 3] {
+            let mut flipped = data.clone();
+            flipped[at] ^= 0x01;
+            assert!(is_corrupted(&flipped), "flipped at {}", at);
+        }
+
+        // Errors of the reader itself are not reported as corruption
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk failure"))
+            }
+        }
+        let reader = Cursor::new(data[..data.len() / 2].to_vec()).chain(Failing);
+        let result = LepStreamDecoder::new(reader).and_then(LepStreamDecoder::into_replay);
+        assert!(matches!(result, Err(LeptonError::Io(_))));
+    }
+
+    #[test]
+    fn test_stream_roundtrip_options() {
+        let replay = long_replay(100);
+        let template = ReplayData {
+            inputs: Vec::new(),
+            ..replay.clone()
+        };
+        let dictionary = b"LEP replay dictionary".repeat(16);
+        for options in [
+            LepEncoderOptions::uncompressed(),
+            LepEncoderOptions::zstd(19),
+            LepEncoderOptions::zstd_dictionary(3, dictionary.clone()),
+        ] {
+            let mut encoder =
+                LepStreamEncoder::with_options(Vec::new(), &template, &options).unwrap();
+            for input in &replay.inputs {
+                encoder.push(input.clone()).unwrap();
+            }
+            let data = encoder.finish().unwrap();
+
+            assert_eq!(
+                LepEncoder::encode_with_options(&replay, &options).unwrap()[..6],
+                data[..6]
+            );
+            let decoder = LepStreamDecoder::with_dictionary(data.as_slice(), &dictionary).unwrap();
+            assert_eq!(decoder.into_replay().unwrap(), replay);
+        }
+    }
+
+    #[test]
+    fn test_stream_decode_v1() {
+        let mut raw = MAGIC.to_vec();
+        raw.push(1);
+        raw.extend_from_slice(&1.0f64.to_le_bytes());
+        raw.extend_from_slice(&[0, 2, 10, 0x10, 20, 0x00]);
+        raw.extend_from_slice(&[0]);
+        raw.extend_from_slice(&(-500i64).to_le_bytes());
+        let data = zstd::encode_all(raw.as_slice(), 3).unwrap();
+
+        let replay = LepStreamDecoder::new(data.as_slice())
+            .unwrap()
+            .into_replay()
+            .unwrap();
+        assert_eq!(replay, LepDecoder::decode(&data).unwrap());
+        assert_eq!(replay.version, 1);
+        assert_eq!(replay.inputs.len(), 2);
+        assert_eq!(replay.start_offset_us, -500);
+    }
+}
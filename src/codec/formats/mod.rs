@@ -1,5 +1,5 @@
 pub mod lep;
 pub mod osu;
 
-pub use lep::{LepDecoder, LepEncoder};
+pub use lep::{LepDecoder, LepEncoder, LepStreamDecoder, LepStreamEncoder};
 pub use osu::{OsuDecoder, OsuEncoder};
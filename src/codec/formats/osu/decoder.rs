@@ -1,10 +1,12 @@
 //! Decoder for osu! replay format.
 
 use super::parser::OsuParser;
-use super::types::GameMode;
+use super::types::{GameMode, OSU_GAME, OSU_MODE_EXTENSION, ReplayEvent, ticks_to_unix_ns};
 use crate::codec::traits::Decoder;
 use crate::error::{LeptonError, LeptonResult};
-use crate::model::{ReplayData, ReplayInput};
+use crate::model::{
+    HealthPoint, JudgementCount, ReplayData, ReplayInput, ReplayMetadata, ScoreSummary,
+};
 
 pub struct OsuDecoder;
 
@@ -12,27 +14,61 @@ impl Decoder for OsuDecoder {
     fn decode(data: &[u8]) -> LeptonResult<ReplayData> {
         let osu_replay = OsuParser::parse(data)?;
 
-        // Validate Mode
-        if osu_replay.mode != GameMode::Mania {
+        if !matches!(osu_replay.mode, GameMode::Mania | GameMode::Taiko) {
             return Err(LeptonError::Custom(format!(
-                "Unsupported game mode: {:?}. Only osu!mania is supported.",
+                "Unsupported game mode: {:?}. Only osu!mania and osu!taiko are supported.",
                 osu_replay.mode
             )));
         }
 
+        // Key bitfield of every frame, one bit per column.
+        // Taiko drums are mapped to 4 columns (see `KeyTaiko::COLUMNS`).
+        // The two `256|-500` frames stable starts replays with have no keys.
+        let frames: Vec<(i32, Option<u32>)> = osu_replay
+            .replay_data
+            .iter()
+            .enumerate()
+            .filter_map(|(index, event)| {
+                let keys = match event {
+                    ReplayEvent::Mania(event) => event.keys.0,
+                    ReplayEvent::Taiko(event) => event.keys.to_columns(),
+                    _ => return None,
+                };
+                let skip = index < 2 && event.is_skip_frame();
+                Some((event.time_delta(), (!skip).then_some(keys)))
+            })
+            .collect();
+
+        // Lazer replays may use custom speeds only recorded in the score info
+        let rate = match &osu_replay.lazer_score_info {
+            Some(info) => info.rate(),
+            None => osu_replay.mods.rate()?,
+        };
+
         // Convert events
         let mut inputs = Vec::new();
         let mut current_keys = 0u32;
-        let mut accumulated_delta_ms = 0i64; // Time since last emitted input in ms
-
-        // osu! timestamps are in ms (technically implementation dependent, but usually ms)
-        // Lepton uses micros for deltas.
-
-        for event in osu_replay.replay_data {
-            let delta_ms = event.time_delta as i64;
-            accumulated_delta_ms += delta_ms;
+        let mut time_ms = 0i64; // Absolute song time of the current frame
+        let mut start_offset_us = 0i64;
+        let mut last_input_us = None; // Absolute time of the last emitted input
+
+        // osu! frame deltas are in ms of song time, so they are already scaled by
+        // DT/HT: a DoubleTime frame 1000ms after the previous one is 1000ms later
+        // in the chart but ~667ms later in wall-clock time.
+        // Lepton uses micros for deltas, also in song time.
+
+        for (time_delta, new_keys) in frames {
+            // Like the client, every delta moves time (break skips are just
+            // large deltas), but skip frames and frames with a negative delta
+            // are not played.
+            time_ms += time_delta as i64;
+            let Some(new_keys) = new_keys else {
+                continue;
+            };
+            if time_delta < 0 {
+                continue;
+            }
 
-            let new_keys = event.keys.0;
             let changed_keys = current_keys ^ new_keys;
 
             if changed_keys == 0 {
@@ -41,51 +77,85 @@ impl Decoder for OsuDecoder {
             }
 
             // check for columns > 15 (Lepton limit)
-            if new_keys > 0xFFFF {
-                if (new_keys & !0xFFFF) != 0 {
-                    return Err(LeptonError::Custom(
-                        "osu!mania key count exceeds Lepton limit (16 keys)".into(),
-                    ));
-                }
+            if (new_keys & !0xFFFF) != 0 {
+                return Err(LeptonError::Custom(
+                    "osu!mania key count exceeds Lepton limit (16 keys)".into(),
+                ));
             }
 
-            let mut first_event_in_frame = true;
+            // Inputs before the chart start (lead-in) move the start offset back
+            let time_us = time_ms * 1000;
+            let previous_us = *last_input_us.get_or_insert_with(|| {
+                start_offset_us = time_us.min(0);
+                start_offset_us
+            });
+            // A negative frame can rewind time behind the last input: clamp
+            // to keep deltas positive.
+            let mut delta_us = (time_us - previous_us).max(0) as u64;
+            last_input_us = Some(previous_us.max(time_us));
 
             for col in 0..16 {
                 let mask = 1 << col;
                 if (changed_keys & mask) != 0 {
                     let is_press = (new_keys & mask) != 0;
-
-                    let delta_us = if first_event_in_frame {
-                        if accumulated_delta_ms < 0 {
-                            0
-                        } else {
-                            (accumulated_delta_ms as u64) * 1000
-                        }
-                    } else {
-                        0
-                    };
-
                     inputs.push(ReplayInput::new(delta_us, col as u8, is_press, false));
-
-                    if first_event_in_frame {
-                        accumulated_delta_ms = 0;
-                        first_event_in_frame = false;
-                    }
+                    // Later changes of the same frame happen at the same time
+                    delta_us = 0;
                 }
             }
 
             current_keys = new_keys;
         }
 
-        Ok(ReplayData::with_params(
-            1.0, // osu! replays are usually 1.0 rate unless modded with DT/HT, but the time deltas are already scaled in some parsers?
-            // Actually, in osu!, DT makes time go faster, so deltas are smaller?
-            // Or deltas are real time?
-            // Usually replay data is stored in "audio time" or "game time".
-            // For now, assume 1.0 and let the engine handle speed.
-            Some(osu_replay.beatmap_hash),
-            inputs,
-        ))
+        let counts = [
+            osu_replay.count_geki,
+            osu_replay.count_300,
+            osu_replay.count_katu,
+            osu_replay.count_100,
+            osu_replay.count_50,
+            osu_replay.count_miss,
+        ];
+        let metadata = ReplayMetadata {
+            player: Some(osu_replay.username).filter(|name| !name.is_empty()),
+            timestamp_ns: ticks_to_unix_ns(osu_replay.timestamp),
+            game: Some(OSU_GAME.to_string()),
+            game_version: Some(osu_replay.game_version.to_string()),
+            mods: osu_replay
+                .mods
+                .acronyms()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            online_id: Some(osu_replay.replay_id).filter(|id| *id != 0),
+            score: Some(ScoreSummary {
+                score: osu_replay.score as u64,
+                max_combo: osu_replay.max_combo as u32,
+                perfect: osu_replay.perfect,
+                judgements: osu_replay
+                    .mode
+                    .judgement_names()
+                    .iter()
+                    .zip(counts)
+                    .map(|(name, count)| JudgementCount::new(*name, count as u32))
+                    .collect(),
+            }),
+            health_graph: osu_replay
+                .life_bar_graph
+                .iter()
+                .map(|point| HealthPoint {
+                    time_us: point.time_ms as i64 * 1000,
+                    health: point.health,
+                })
+                .collect(),
+        };
+
+        let mut replay = ReplayData::with_params(rate, Some(osu_replay.beatmap_hash), inputs)
+            .with_start_offset(start_offset_us)
+            .with_metadata(metadata);
+        // Columns do not tell taiko from mania: keep the mode for the encoder
+        replay
+            .extensions
+            .insert(OSU_MODE_EXTENSION.to_string(), vec![osu_replay.mode as u8]);
+        Ok(replay)
     }
 }
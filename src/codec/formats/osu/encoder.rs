@@ -1,7 +1,12 @@
 //! Encoder for osu! replay format.
 
+use super::lazer::{LAZER_FIRST_VERSION, LazerMod, LazerScoreInfo};
+use super::mods::OsuMods;
 use super::parser::OsuParser;
-use super::types::{GameMode, KeyMania, OsuReplay, ReplayEventMania};
+use super::types::{
+    GameMode, KeyMania, KeyTaiko, LifeBarPoint, OSU_GAME, OSU_MODE_EXTENSION, OsuReplay,
+    ReplayEvent, ReplayEventMania, ReplayEventTaiko, unix_ns_to_ticks,
+};
 use crate::codec::traits::Encoder;
 use crate::error::{LeptonError, LeptonResult};
 use crate::model::ReplayData;
@@ -9,22 +14,151 @@ use crate::model::ReplayData;
 pub struct OsuEncoder;
 
 impl Encoder for OsuEncoder {
+    /// Encodes a replay in the osu! mode recorded by `OsuDecoder` (the
+    /// `OSU_MODE_EXTENSION` entry), or as osu!mania for replays without one.
+    /// Use `OsuEncoder::encode_mode` to pick the mode.
     fn encode(chart: &ReplayData) -> LeptonResult<Vec<u8>> {
+        let mode = match chart.extensions.get(OSU_MODE_EXTENSION).map(Vec::as_slice) {
+            Some(&[mode]) => GameMode::from(mode),
+            _ => GameMode::Mania,
+        };
+        Self::encode_mode(chart, mode)
+    }
+}
+
+impl OsuEncoder {
+    /// Encodes a replay for an osu! mode.
+    ///
+    /// osu!taiko replays take columns 0-3 as left kat, left don, right don and
+    /// right kat (see `KeyTaiko::COLUMNS`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for modes other than mania and taiko, columns the mode
+    /// cannot represent, recorded mods osu! does not allow together (see
+    /// `OsuMods::validate`), or rates neither osu! stable nor osu!lazer can
+    /// represent.
+    /// Rates only lazer can represent are written in a lazer score info block.
+    pub fn encode_mode(chart: &ReplayData, mode: GameMode) -> LeptonResult<Vec<u8>> {
+        let max_column = match mode {
+            GameMode::Mania => 15,
+            GameMode::Taiko => 3,
+            mode => {
+                return Err(LeptonError::Custom(format!(
+                    "Unsupported game mode: {:?}. Only osu!mania and osu!taiko are supported.",
+                    mode
+                )));
+            }
+        };
+
         // Convert ReplayData to OsuReplay
+        // Deltas are song time on both sides; the rate only selects the mods.
+        let metadata = &chart.metadata;
+        // Mods, game version and counts only make sense when the replay comes
+        // from osu!: other games reuse acronyms like MR or HD for other mods.
+        let from_osu = metadata.game.as_deref() == Some(OSU_GAME);
+        let mut mods = metadata
+            .mods
+            .iter()
+            .filter(|_| from_osu)
+            .filter_map(|acronym| OsuMods::from_acronym(acronym))
+            .fold(OsuMods::NONE, |mods, mod_| mods | mod_);
+        // The rate wins over recorded speed mods that disagree with it
+        let mut lazer_score_info = None;
+        if mods.rate().ok() != Some(chart.rate) {
+            mods.remove(OsuMods::DOUBLE_TIME | OsuMods::NIGHTCORE | OsuMods::HALF_TIME);
+            match OsuMods::from_rate(chart.rate) {
+                Ok(speed_mod) => mods |= speed_mod,
+                // Custom speeds only exist in osu!lazer: keep DT/HT in the legacy
+                // mods and record the exact speed in a lazer score info block.
+                Err(_) => {
+                    let mut info = LazerScoreInfo {
+                        online_id: metadata.online_id.unwrap_or(0),
+                        mods: mods
+                            .acronyms()
+                            .into_iter()
+                            .map(|acronym| LazerMod {
+                                acronym: acronym.to_string(),
+                                settings: Default::default(),
+                            })
+                            .collect(),
+                        ..Default::default()
+                    };
+                    info.set_rate(chart.rate)?;
+                    mods |= if chart.rate > 1.0 {
+                        OsuMods::DOUBLE_TIME
+                    } else {
+                        OsuMods::HALF_TIME
+                    };
+                    lazer_score_info = Some(info);
+                }
+            }
+        }
+
+        mods.validate()?;
+
+        let game_version = metadata
+            .game_version
+            .as_deref()
+            .filter(|_| from_osu)
+            .and_then(|version| version.parse().ok())
+            .unwrap_or(20240101);
+        let game_version = if lazer_score_info.is_some() {
+            game_version.max(LAZER_FIRST_VERSION)
+        } else {
+            game_version
+        };
+        let score = metadata
+            .score
+            .clone()
+            .filter(|_| from_osu)
+            .unwrap_or_default();
+        let names = mode.judgement_names();
+        let count = |i: usize| score.count(names[i]).min(u16::MAX as u32) as u16;
 
         // 1. Reconstruct events
+        let frame = |time_delta: i32, keys: u32| match mode {
+            GameMode::Taiko => ReplayEvent::Taiko(ReplayEventTaiko {
+                time_delta,
+                x: 0.0,
+                y: 0.0,
+                keys: KeyTaiko::from_columns(keys),
+            }),
+            _ => ReplayEvent::Mania(ReplayEventMania {
+                time_delta,
+                keys: KeyMania(keys),
+                y: 0.0,
+            }),
+        };
         let mut events = Vec::new();
         let mut current_keys = 0u32;
+        // Frame deltas are whole ms: round the absolute time and emit the
+        // difference, so truncation never accumulates over the replay.
+        let mut time_us = chart.start_offset_us;
+        let mut time_ms = 0i64;
 
         for input in &chart.inputs {
             let col = input.column();
-            if col > 15 {
-                return Err(LeptonError::Custom(
-                    "Input column exceeds osu!mania 16k limit".into(),
-                ));
+            if col > max_column {
+                return Err(LeptonError::Custom(format!(
+                    "Input column {} exceeds the {:?} limit of {} keys",
+                    col,
+                    mode,
+                    max_column + 1
+                )));
             }
 
-            let delta_us = input.delta_us;
+            time_us += input.delta_us as i64;
+            let rounded_ms = (time_us + 500).div_euclid(1000);
+            let mut time_delta = (rounded_ms - time_ms) as i32;
+            time_ms = rounded_ms;
+
+            // osu! does not play frames with a negative delta: reach a negative
+            // start offset with an empty frame, then press at delta 0.
+            if time_delta < 0 {
+                events.push(frame(time_delta, current_keys));
+                time_delta = 0;
+            }
 
             // Apply change
             let mask = 1 << col;
@@ -34,34 +168,45 @@ impl Encoder for OsuEncoder {
                 current_keys &= !mask;
             }
 
-            events.push(ReplayEventMania {
-                time_delta: (delta_us as i64 / 1000) as i32,
-                keys: KeyMania(current_keys),
-            });
+            events.push(frame(time_delta, current_keys));
         }
 
-        let osu_replay = OsuReplay {
-            mode: GameMode::Mania,
-            game_version: 20240101, // Default or dummy
+        let mut osu_replay = OsuReplay {
+            mode,
+            game_version,
             beatmap_hash: chart.hash.clone().unwrap_or_default(),
-            username: "LeptonUser".to_string(), // Placeholder or from config?
-            replay_hash: String::new(),         // Recalculate?
-            count_300: 0,
-            count_100: 0,
-            count_50: 0,
-            count_geki: 0,
-            count_katu: 0,
-            count_miss: 0,
-            score: 0,
-            max_combo: 0,
-            perfect: true,
-            mods: 0,
-            life_bar_graph: String::new(),
-            timestamp: 0,
+            username: metadata
+                .player
+                .clone()
+                .unwrap_or_else(|| "LeptonUser".to_string()),
+            replay_hash: String::new(),
+            count_300: count(1),
+            count_100: count(3),
+            count_50: count(4),
+            count_geki: count(0),
+            count_katu: count(2),
+            count_miss: count(5),
+            score: score.score.min(u32::MAX as u64) as u32,
+            max_combo: score.max_combo.min(u16::MAX as u32) as u16,
+            perfect: score.perfect || metadata.score.is_none(),
+            mods,
+            life_bar_graph: metadata
+                .health_graph
+                .iter()
+                .map(|point| LifeBarPoint {
+                    time_ms: (point.time_us / 1000) as i32,
+                    health: point.health,
+                })
+                .collect(),
+            timestamp: metadata.timestamp_ns.map_or(0, unix_ns_to_ticks),
             replay_data: events,
-            replay_id: 0,
+            rng_seed: Some(0),
+            replay_id: metadata.online_id.unwrap_or(0),
+            lazer_score_info,
         };
 
+        osu_replay.replay_hash = OsuParser::compute_replay_hash(&osu_replay);
+
         OsuParser::encode(&osu_replay)
     }
 }
@@ -0,0 +1,137 @@
+//! osu! letter grades, as stored in the replay hash.
+
+use super::mods::OsuMods;
+use super::types::{GameMode, OsuReplay};
+
+/// osu! letter grade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OsuGrade {
+    D,
+    C,
+    B,
+    A,
+    S,
+    /// Silver S (Hidden / Flashlight).
+    SH,
+    SS,
+    /// Silver SS (Hidden / Flashlight).
+    SSH,
+}
+
+impl OsuGrade {
+    /// Computes the osu!mania grade from an accuracy between 0.0 and 1.0.
+    ///
+    /// `silver` selects the silver SS/S variants awarded with Hidden or Flashlight.
+    #[must_use]
+    pub fn from_accuracy(accuracy: f64, silver: bool) -> Self {
+        if accuracy >= 1.0 {
+            if silver { OsuGrade::SSH } else { OsuGrade::SS }
+        } else if accuracy > 0.95 {
+            if silver { OsuGrade::SH } else { OsuGrade::S }
+        } else if accuracy > 0.90 {
+            OsuGrade::A
+        } else if accuracy > 0.80 {
+            OsuGrade::B
+        } else if accuracy > 0.70 {
+            OsuGrade::C
+        } else {
+            OsuGrade::D
+        }
+    }
+
+    /// Computes the grade osu! stable gives a replay from its judgement
+    /// counts and mods, with the rules of its mode:
+    /// - osu!mania grades on accuracy, geki and katu being MAX and 200.
+    /// - osu!standard and osu!taiko grade on the ratio of 300s, with no miss
+    ///   for S and few 50s; geki and katu are not judgements there.
+    /// - osu!catch grades on the ratio of caught fruits, katu being missed
+    ///   droplets.
+    #[must_use]
+    pub fn from_replay(replay: &OsuReplay) -> Self {
+        let silver = replay
+            .mods
+            .intersects(OsuMods::HIDDEN | OsuMods::FLASHLIGHT | OsuMods::FADE_IN);
+        let [geki, c300, katu, c100, c50, miss] = [
+            replay.count_geki,
+            replay.count_300,
+            replay.count_katu,
+            replay.count_100,
+            replay.count_50,
+            replay.count_miss,
+        ]
+        .map(u64::from);
+
+        match replay.mode {
+            GameMode::Mania => {
+                let total = geki + c300 + katu + c100 + c50 + miss;
+                let points = 300 * (geki + c300) + 200 * katu + 100 * c100 + 50 * c50;
+                Self::from_accuracy(ratio(points, 300 * total), silver)
+            }
+            GameMode::Std | GameMode::Taiko => {
+                let total = c300 + c100 + c50 + miss;
+                Self::from_hit_ratios(ratio(c300, total), ratio(c50, total), miss, silver)
+            }
+            GameMode::Catch => {
+                let caught = c300 + c100 + c50;
+                Self::from_catch_accuracy(ratio(caught, caught + katu + miss), silver)
+            }
+        }
+    }
+
+    /// osu!standard and osu!taiko grade from the ratios of 300s and 50s.
+    fn from_hit_ratios(ratio_300: f64, ratio_50: f64, misses: u64, silver: bool) -> Self {
+        if ratio_300 >= 1.0 {
+            if silver { OsuGrade::SSH } else { OsuGrade::SS }
+        } else if ratio_300 > 0.9 && ratio_50 <= 0.01 && misses == 0 {
+            if silver { OsuGrade::SH } else { OsuGrade::S }
+        } else if (ratio_300 > 0.8 && misses == 0) || ratio_300 > 0.9 {
+            OsuGrade::A
+        } else if (ratio_300 > 0.7 && misses == 0) || ratio_300 > 0.8 {
+            OsuGrade::B
+        } else if ratio_300 > 0.6 {
+            OsuGrade::C
+        } else {
+            OsuGrade::D
+        }
+    }
+
+    /// osu!catch grade from the ratio of caught fruits, droplets and drops.
+    fn from_catch_accuracy(accuracy: f64, silver: bool) -> Self {
+        if accuracy >= 1.0 {
+            if silver { OsuGrade::SSH } else { OsuGrade::SS }
+        } else if accuracy > 0.98 {
+            if silver { OsuGrade::SH } else { OsuGrade::S }
+        } else if accuracy > 0.94 {
+            OsuGrade::A
+        } else if accuracy > 0.90 {
+            OsuGrade::B
+        } else if accuracy > 0.85 {
+            OsuGrade::C
+        } else {
+            OsuGrade::D
+        }
+    }
+
+    /// Returns the name osu! stable uses for the grade (`X` is SS, `XH` silver SS).
+    #[must_use]
+    pub fn stable_name(self) -> &'static str {
+        match self {
+            OsuGrade::D => "D",
+            OsuGrade::C => "C",
+            OsuGrade::B => "B",
+            OsuGrade::A => "A",
+            OsuGrade::S => "S",
+            OsuGrade::SH => "SH",
+            OsuGrade::SS => "X",
+            OsuGrade::SSH => "XH",
+        }
+    }
+}
+
+/// `part / total`, or 1.0 for a play without judgements.
+fn ratio(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 1.0;
+    }
+    part as f64 / total as f64
+}
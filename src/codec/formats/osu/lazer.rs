@@ -0,0 +1,158 @@
+//! osu!lazer score info block.
+//!
+//! Replays written by osu!lazer append a LZMA-compressed JSON block after the
+//! replay ID. It carries exact statistics, mods with their settings (e.g. a
+//! custom DoubleTime speed) and the client version.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{LeptonError, LeptonResult};
+
+/// First replay version written by osu!lazer. Replays from this version on
+/// may carry a score info block.
+pub const LAZER_FIRST_VERSION: u32 = 30000001;
+
+/// Speed change range of the lazer DoubleTime/Nightcore mods.
+const SPEED_UP_RANGE: (f64, f64) = (1.01, 2.0);
+
+/// Speed change range of the lazer HalfTime/Daycore mods.
+const SLOW_DOWN_RANGE: (f64, f64) = (0.5, 0.99);
+
+/// A lazer mod with its settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LazerMod {
+    pub acronym: String,
+
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub settings: Map<String, Value>,
+}
+
+impl LazerMod {
+    /// Returns the `speed_change` setting, if set.
+    #[must_use]
+    pub fn speed_change(&self) -> Option<f64> {
+        self.settings.get("speed_change").and_then(Value::as_f64)
+    }
+}
+
+/// Score info block of an osu!lazer replay.
+///
+/// Fields Lepton does not use are kept in `extra` so the block is written
+/// back unchanged.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LazerScoreInfo {
+    #[serde(default)]
+    pub online_id: i64,
+
+    #[serde(default)]
+    pub mods: Vec<LazerMod>,
+
+    /// Exact judgement counts by lazer result name (`great`, `ok`, `miss`...).
+    #[serde(default)]
+    pub statistics: BTreeMap<String, u32>,
+
+    #[serde(default)]
+    pub maximum_statistics: BTreeMap<String, u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_version: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl LazerScoreInfo {
+    /// Parses the decompressed JSON block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is invalid.
+    pub fn from_json(json: &[u8]) -> LeptonResult<Self> {
+        Ok(serde_json::from_slice(json)?)
+    }
+
+    /// Serializes the block to JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> LeptonResult<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Returns the playback rate set by the speed mods.
+    ///
+    /// DoubleTime/Nightcore default to 1.5 and HalfTime/Daycore to 0.75 when
+    /// no `speed_change` is set.
+    #[must_use]
+    pub fn rate(&self) -> f64 {
+        self.mods
+            .iter()
+            .find_map(|mod_| {
+                let default = match mod_.acronym.as_str() {
+                    "DT" | "NC" => 1.5,
+                    "HT" | "DC" => 0.75,
+                    _ => return None,
+                };
+                Some(mod_.speed_change().unwrap_or(default))
+            })
+            .unwrap_or(1.0)
+    }
+
+    /// Replaces the speed mods with one playing at `rate`.
+    ///
+    /// Keeps Nightcore/Daycore if already present, otherwise uses
+    /// DoubleTime/HalfTime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if lazer's speed mods cannot play at `rate`.
+    pub fn set_rate(&mut self, rate: f64) -> LeptonResult<()> {
+        let speed_mods = ["DT", "NC", "HT", "DC"];
+        let previous = self
+            .mods
+            .iter()
+            .position(|mod_| speed_mods.contains(&mod_.acronym.as_str()))
+            .map(|index| self.mods.remove(index));
+
+        if rate == 1.0 {
+            return Ok(());
+        }
+
+        let in_range = |(min, max): (f64, f64)| (min..=max).contains(&rate);
+        let acronym = match previous.as_ref().map(|mod_| mod_.acronym.as_str()) {
+            Some("NC") if rate > 1.0 => "NC",
+            Some("DC") if rate < 1.0 => "DC",
+            _ if rate > 1.0 => "DT",
+            _ => "HT",
+        };
+        let range = if rate > 1.0 {
+            SPEED_UP_RANGE
+        } else {
+            SLOW_DOWN_RANGE
+        };
+        if !in_range(range) {
+            return Err(LeptonError::Custom(format!(
+                "Rate {} cannot be represented in an osu!lazer replay ({} to {})",
+                rate, range.0, range.1
+            )));
+        }
+
+        let mut settings = previous
+            .filter(|mod_| mod_.acronym == acronym)
+            .map(|mod_| mod_.settings)
+            .unwrap_or_default();
+        settings.insert("speed_change".to_string(), Value::from(rate));
+        self.mods.insert(
+            0,
+            LazerMod {
+                acronym: acronym.to_string(),
+                settings,
+            },
+        );
+        Ok(())
+    }
+}
@@ -1,10 +1,16 @@
 pub mod decoder;
 pub mod encoder;
+pub mod grade;
+pub mod lazer;
+pub mod mods;
 pub mod parser;
 pub mod types;
 
 pub use decoder::OsuDecoder;
 pub use encoder::OsuEncoder;
+pub use grade::OsuGrade;
+pub use lazer::LazerScoreInfo;
+pub use mods::OsuMods;
 
 #[cfg(test)]
 mod tests;
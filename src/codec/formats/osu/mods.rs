@@ -0,0 +1,343 @@
+//! osu! mods bitfield.
+
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+use std::str::FromStr;
+
+use crate::error::{LeptonError, LeptonResult};
+
+/// Set of osu! mods, stored as the replay `mods` bitfield.
+///
+/// Nightcore is always stored together with DoubleTime, and Perfect together
+/// with SuddenDeath, like the osu! client does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct OsuMods(pub u32);
+
+impl OsuMods {
+    pub const NONE: Self = Self(0);
+    pub const NO_FAIL: Self = Self(1 << 0);
+    pub const EASY: Self = Self(1 << 1);
+    pub const TOUCH_DEVICE: Self = Self(1 << 2);
+    pub const HIDDEN: Self = Self(1 << 3);
+    pub const HARD_ROCK: Self = Self(1 << 4);
+    pub const SUDDEN_DEATH: Self = Self(1 << 5);
+    pub const DOUBLE_TIME: Self = Self(1 << 6);
+    pub const RELAX: Self = Self(1 << 7);
+    pub const HALF_TIME: Self = Self(1 << 8);
+    pub const NIGHTCORE: Self = Self(1 << 9);
+    pub const FLASHLIGHT: Self = Self(1 << 10);
+    pub const AUTOPLAY: Self = Self(1 << 11);
+    pub const SPUN_OUT: Self = Self(1 << 12);
+    pub const AUTOPILOT: Self = Self(1 << 13);
+    pub const PERFECT: Self = Self(1 << 14);
+    pub const KEY4: Self = Self(1 << 15);
+    pub const KEY5: Self = Self(1 << 16);
+    pub const KEY6: Self = Self(1 << 17);
+    pub const KEY7: Self = Self(1 << 18);
+    pub const KEY8: Self = Self(1 << 19);
+    pub const FADE_IN: Self = Self(1 << 20);
+    pub const RANDOM: Self = Self(1 << 21);
+    pub const CINEMA: Self = Self(1 << 22);
+    pub const TARGET: Self = Self(1 << 23);
+    pub const KEY9: Self = Self(1 << 24);
+    pub const KEY_COOP: Self = Self(1 << 25);
+    pub const KEY1: Self = Self(1 << 26);
+    pub const KEY3: Self = Self(1 << 27);
+    pub const KEY2: Self = Self(1 << 28);
+    pub const SCORE_V2: Self = Self(1 << 29);
+    pub const MIRROR: Self = Self(1 << 30);
+
+    /// Every nK key mod, with its key count.
+    pub const KEY_MODS: [(Self, u8); 9] = [
+        (Self::KEY1, 1),
+        (Self::KEY2, 2),
+        (Self::KEY3, 3),
+        (Self::KEY4, 4),
+        (Self::KEY5, 5),
+        (Self::KEY6, 6),
+        (Self::KEY7, 7),
+        (Self::KEY8, 8),
+        (Self::KEY9, 9),
+    ];
+
+    /// Acronyms in display order (bit order).
+    const ACRONYMS: [(Self, &'static str); 31] = [
+        (Self::NO_FAIL, "NF"),
+        (Self::EASY, "EZ"),
+        (Self::TOUCH_DEVICE, "TD"),
+        (Self::HIDDEN, "HD"),
+        (Self::HARD_ROCK, "HR"),
+        (Self::SUDDEN_DEATH, "SD"),
+        (Self::DOUBLE_TIME, "DT"),
+        (Self::RELAX, "RX"),
+        (Self::HALF_TIME, "HT"),
+        (Self::NIGHTCORE, "NC"),
+        (Self::FLASHLIGHT, "FL"),
+        (Self::AUTOPLAY, "AT"),
+        (Self::SPUN_OUT, "SO"),
+        (Self::AUTOPILOT, "AP"),
+        (Self::PERFECT, "PF"),
+        (Self::KEY4, "4K"),
+        (Self::KEY5, "5K"),
+        (Self::KEY6, "6K"),
+        (Self::KEY7, "7K"),
+        (Self::KEY8, "8K"),
+        (Self::FADE_IN, "FI"),
+        (Self::RANDOM, "RD"),
+        (Self::CINEMA, "CN"),
+        (Self::TARGET, "TP"),
+        (Self::KEY9, "9K"),
+        (Self::KEY_COOP, "CO"),
+        (Self::KEY1, "1K"),
+        (Self::KEY3, "3K"),
+        (Self::KEY2, "2K"),
+        (Self::SCORE_V2, "V2"),
+        (Self::MIRROR, "MR"),
+    ];
+
+    /// Rate of DoubleTime and Nightcore.
+    pub const DOUBLE_TIME_RATE: f64 = 1.5;
+
+    /// Rate of HalfTime.
+    pub const HALF_TIME_RATE: f64 = 0.75;
+
+    /// Returns the raw bitfield.
+    #[must_use]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Checks if every mod of `other` is set.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Checks if any mod of `other` is set.
+    #[must_use]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    /// Returns the acronym of every set mod, in bit order.
+    ///
+    /// Unlike `Display`, implied mods are listed too (`NC` comes with `DT`).
+    #[must_use]
+    pub fn acronyms(self) -> Vec<&'static str> {
+        Self::ACRONYMS
+            .iter()
+            .filter(|(mod_, _)| self.contains(*mod_))
+            .map(|(_, acronym)| *acronym)
+            .collect()
+    }
+
+    /// Parses a single acronym (case-insensitive), with the mods it implies:
+    /// NC also sets DT and PF also sets SD, like the client.
+    #[must_use]
+    pub fn from_acronym(acronym: &str) -> Option<Self> {
+        Self::ACRONYMS
+            .iter()
+            .find(|(_, a)| a.eq_ignore_ascii_case(acronym))
+            .map(|(mod_, _)| mod_.with_implied())
+    }
+
+    /// Adds the mods implied by NC (DT) and PF (SD).
+    fn with_implied(self) -> Self {
+        let mut mods = self;
+        if self.contains(Self::NIGHTCORE) {
+            mods |= Self::DOUBLE_TIME;
+        }
+        if self.contains(Self::PERFECT) {
+            mods |= Self::SUDDEN_DEATH;
+        }
+        mods
+    }
+
+    /// Returns the key count forced by an nK key mod, if any.
+    #[must_use]
+    pub fn key_count(self) -> Option<u8> {
+        Self::KEY_MODS
+            .iter()
+            .find(|(key_mod, _)| self.contains(*key_mod))
+            .map(|(_, keys)| *keys)
+    }
+
+    /// Returns the playback rate implied by the speed mods.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if both a speed-up (DT/NC) and HalfTime are set.
+    pub fn rate(self) -> LeptonResult<f64> {
+        let speed_up = self.intersects(Self::DOUBLE_TIME | Self::NIGHTCORE);
+        let slow_down = self.contains(Self::HALF_TIME);
+
+        match (speed_up, slow_down) {
+            (false, false) => Ok(1.0),
+            (true, false) => Ok(Self::DOUBLE_TIME_RATE),
+            (false, true) => Ok(Self::HALF_TIME_RATE),
+            (true, true) => Err(LeptonError::Custom(
+                "Invalid osu! mods: DoubleTime/Nightcore and HalfTime are both set".into(),
+            )),
+        }
+    }
+
+    /// Returns the speed mod representing a playback rate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if osu! has no mod for the rate (only 1.0, 1.5 and 0.75 exist).
+    pub fn from_rate(rate: f64) -> LeptonResult<Self> {
+        if rate == 1.0 {
+            Ok(Self::NONE)
+        } else if rate == Self::DOUBLE_TIME_RATE {
+            Ok(Self::DOUBLE_TIME)
+        } else if rate == Self::HALF_TIME_RATE {
+            Ok(Self::HALF_TIME)
+        } else {
+            Err(LeptonError::Custom(format!(
+                "Rate {} cannot be represented in an osu! replay (only 1.0, 1.5 and 0.75)",
+                rate
+            )))
+        }
+    }
+
+    /// Checks that the mods can be played together.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first illegal combination found.
+    pub fn validate(self) -> LeptonResult<()> {
+        let exclusive = [
+            (Self::DOUBLE_TIME | Self::NIGHTCORE, Self::HALF_TIME),
+            (Self::EASY, Self::HARD_ROCK),
+            (Self::NO_FAIL, Self::SUDDEN_DEATH | Self::PERFECT),
+            (Self::RELAX, Self::AUTOPILOT),
+            (Self::AUTOPLAY, Self::RELAX | Self::AUTOPILOT),
+            (Self::HIDDEN, Self::FADE_IN),
+        ];
+        for (a, b) in exclusive {
+            if self.intersects(a) && self.intersects(b) {
+                return Err(LeptonError::Custom(format!(
+                    "Invalid osu! mods: {} cannot be combined with {}",
+                    Self(self.0 & a.0),
+                    Self(self.0 & b.0)
+                )));
+            }
+        }
+
+        let key_mods = Self::KEY_MODS
+            .iter()
+            .filter(|(key_mod, _)| self.contains(*key_mod))
+            .count();
+        if key_mods > 1 {
+            return Err(LeptonError::Custom(format!(
+                "Invalid osu! mods: {} sets more than one key mod",
+                self
+            )));
+        }
+
+        if self.contains(Self::NIGHTCORE) && !self.contains(Self::DOUBLE_TIME) {
+            return Err(LeptonError::Custom(
+                "Invalid osu! mods: Nightcore is set without DoubleTime".into(),
+            ));
+        }
+        if self.contains(Self::PERFECT) && !self.contains(Self::SUDDEN_DEATH) {
+            return Err(LeptonError::Custom(
+                "Invalid osu! mods: Perfect is set without SuddenDeath".into(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl BitOr for OsuMods {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for OsuMods {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl From<u32> for OsuMods {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<OsuMods> for u32 {
+    fn from(value: OsuMods) -> Self {
+        value.0
+    }
+}
+
+/// Formats the mods as acronyms (e.g. `HDDT`), or `NM` when empty.
+///
+/// DoubleTime is implied by Nightcore and SuddenDeath by Perfect, so they are
+/// not repeated (`HDNC`, not `HDDTNC`).
+impl fmt::Display for OsuMods {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("NM");
+        }
+
+        for (mod_, acronym) in Self::ACRONYMS {
+            let implied = (mod_ == Self::DOUBLE_TIME && self.contains(Self::NIGHTCORE))
+                || (mod_ == Self::SUDDEN_DEATH && self.contains(Self::PERFECT));
+            if self.contains(mod_) && !implied {
+                f.write_str(acronym)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses acronyms such as `HDDT` or `hd,nc` (case-insensitive).
+///
+/// `NC` also sets DoubleTime and `PF` also sets SuddenDeath. Combinations are
+/// not validated; use `OsuMods::validate`.
+impl FromStr for OsuMods {
+    type Err = LeptonError;
+
+    fn from_str(s: &str) -> LeptonResult<Self> {
+        let letters: Vec<char> = s
+            .chars()
+            .filter(|c| !c.is_whitespace() && !matches!(c, ',' | '+' | '|'))
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        if !letters.len().is_multiple_of(2) {
+            return Err(LeptonError::Custom(format!("Invalid osu! mods '{}'", s)));
+        }
+
+        let mut mods = Self::NONE;
+        for pair in letters.chunks(2) {
+            let acronym: String = pair.iter().collect();
+            if acronym == "NM" {
+                continue;
+            }
+
+            mods |= Self::from_acronym(&acronym)
+                .ok_or_else(|| LeptonError::Custom(format!("Unknown osu! mod '{}'", acronym)))?;
+        }
+        Ok(mods)
+    }
+}
@@ -1,6 +1,12 @@
 //! Parser logic for osu! replay format.
 
-use super::types::{GameMode, KeyMania, OsuReplay, ReplayEventMania};
+use super::grade::OsuGrade;
+use super::lazer::{LAZER_FIRST_VERSION, LazerScoreInfo};
+use super::mods::OsuMods;
+use super::types::{
+    GameMode, KeyMania, KeyStd, KeyTaiko, LifeBarPoint, OsuReplay, ReplayEvent, ReplayEventCatch,
+    ReplayEventMania, ReplayEventStd, ReplayEventTaiko,
+};
 use crate::error::{LeptonError, LeptonResult};
 use std::io::{Cursor, Read, Write};
 
@@ -44,10 +50,10 @@ impl OsuParser {
         let perfect = read_u8(&mut reader)? != 0;
 
         // 10. Mods (u32)
-        let mods = read_u32(&mut reader)?;
+        let mods = OsuMods(read_u32(&mut reader)?);
 
         // 11. Life Bar Graph (String)
-        let life_bar_graph = read_string(&mut reader)?;
+        let life_bar_graph = parse_life_bar(&read_string(&mut reader)?);
 
         // 12. Timestamp (u64)
         let timestamp = read_u64(&mut reader)?;
@@ -75,15 +81,30 @@ impl OsuParser {
             0
         };
 
+        // 16. osu!lazer Score Info (i32 length + LZMA JSON) - Optional/Trailing
+        let lazer_score_info =
+            if version >= LAZER_FIRST_VERSION && reader.position() + 4 <= data.len() as u64 {
+                let len = read_u32(&mut reader)? as i32;
+                if len > 0 {
+                    let mut compressed_info = vec![0u8; len as usize];
+                    reader
+                        .read_exact(&mut compressed_info)
+                        .map_err(LeptonError::Io)?;
+                    let json = liblzma::decode_all(&compressed_info[..])
+                        .map_err(|e| LeptonError::Custom(format!("LZMA Error: {}", e)))?;
+                    Some(LazerScoreInfo::from_json(&json)?)
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+
         // Parse Replay Data String
         let replay_string = String::from_utf8(decompressed_data)
             .map_err(|e| LeptonError::Custom(format!("Invalid UTF-8 in replay data: {}", e)))?;
 
-        let replay_events = if mode == GameMode::Mania {
-            parse_mania_data(&replay_string)?
-        } else {
-            Vec::new()
-        };
+        let (replay_events, rng_seed) = parse_replay_data(mode, &replay_string)?;
 
         Ok(OsuReplay {
             mode,
@@ -104,7 +125,9 @@ impl OsuParser {
             life_bar_graph,
             timestamp,
             replay_data: replay_events,
+            rng_seed,
             replay_id: replay_id as i64,
+            lazer_score_info,
         })
     }
 
@@ -144,16 +167,16 @@ impl OsuParser {
         write_u8(&mut writer, if replay.perfect { 1 } else { 0 })?;
 
         // 10. Mods (u32)
-        write_u32(&mut writer, replay.mods)?;
+        write_u32(&mut writer, replay.mods.bits())?;
 
         // 11. Life Bar Graph (String)
-        write_string(&mut writer, &replay.life_bar_graph)?;
+        write_string(&mut writer, &encode_life_bar(&replay.life_bar_graph))?;
 
         // 12. Timestamp (u64)
         write_u64(&mut writer, replay.timestamp)?;
 
         // Create Replay Data String
-        let replay_string = encode_mania_data(&replay.replay_data);
+        let replay_string = encode_replay_data(&replay.replay_data, replay.rng_seed);
 
         // Compress LZMA
         let compressed_data = liblzma::encode_all(replay_string.as_bytes(), 6) // Level 6 default
@@ -170,8 +193,41 @@ impl OsuParser {
         // 15. Replay ID (u64)
         write_u64(&mut writer, replay.replay_id as u64)?;
 
+        // 16. osu!lazer Score Info (i32 length + LZMA JSON)
+        if let Some(info) = &replay.lazer_score_info {
+            let compressed_info = liblzma::encode_all(info.to_json()?.as_slice(), 6)
+                .map_err(|e| LeptonError::Custom(format!("LZMA compression error: {}", e)))?;
+            write_u32(&mut writer, compressed_info.len() as u32)?;
+            writer
+                .write_all(&compressed_info)
+                .map_err(LeptonError::Io)?;
+        }
+
         Ok(writer.into_inner())
     }
+
+    /// Computes the replay MD5 the way osu! stable does:
+    /// `md5("{max_combo}osu{username}{beatmap_hash}{score}{rank}")`, the rank
+    /// being derived from the judgement counts and mods with the rules of the
+    /// replay's mode (see `OsuGrade::from_replay`).
+    #[must_use]
+    pub fn compute_replay_hash(replay: &OsuReplay) -> String {
+        let rank = OsuGrade::from_replay(replay).stable_name();
+
+        let input = format!(
+            "{}osu{}{}{}{}",
+            replay.max_combo, replay.username, replay.beatmap_hash, replay.score, rank
+        );
+        format!("{:x}", md5::compute(input))
+    }
+
+    /// Checks that the replay hash matches the rest of the replay.
+    #[must_use]
+    pub fn verify_replay_hash(replay: &OsuReplay) -> bool {
+        replay
+            .replay_hash
+            .eq_ignore_ascii_case(&Self::compute_replay_hash(replay))
+    }
 }
 
 // --- Helper Functions ---
@@ -216,11 +272,24 @@ fn read_string(reader: &mut Cursor<&[u8]>) -> LeptonResult<String> {
     let mut buf = vec![0u8; len as usize];
     reader.read_exact(&mut buf).map_err(LeptonError::Io)?;
 
-    Ok(String::from_utf8(buf).map_err(LeptonError::Utf8)?)
+    String::from_utf8(buf).map_err(LeptonError::Utf8)
 }
 
-fn parse_mania_data(data: &str) -> LeptonResult<Vec<ReplayEventMania>> {
+/// Parses the `w|x|y|z,` frames of the replay data.
+///
+/// `w` is the time delta in ms. The meaning of the other fields depends on
+/// the mode:
+/// - std: cursor `x`/`y`, keys in `z`
+/// - taiko: drum keys in `z`
+/// - catch: catcher position in `x`, dash in `z`
+/// - mania: key bitfield in `x`
+///
+/// The `-12345|0|0|seed` frame holds the RNG seed and is returned apart.
+/// Every other frame is kept, including the two `256|-500` frames osu!
+/// stable writes at the start of a replay.
+fn parse_replay_data(mode: GameMode, data: &str) -> LeptonResult<(Vec<ReplayEvent>, Option<i32>)> {
     let mut events = Vec::new();
+    let mut rng_seed = None;
     for action in data.split(',') {
         if action.is_empty() {
             continue;
@@ -230,19 +299,65 @@ fn parse_mania_data(data: &str) -> LeptonResult<Vec<ReplayEventMania>> {
             continue;
         }
 
-        let delta = parts[0].parse::<i64>().unwrap_or(0);
-        if delta == -12345 {
+        let time_delta = parts[0].parse::<i32>().unwrap_or(0);
+        if time_delta == -12345 {
+            rng_seed = parts[3].parse::<i32>().ok();
             continue;
         }
 
-        let keys = parts[3].parse::<u32>().unwrap_or(0);
-
-        events.push(ReplayEventMania {
-            time_delta: delta as i32,
-            keys: KeyMania(keys),
+        let x = parts[1].parse::<f32>().unwrap_or(0.0);
+        let y = parts[2].parse::<f32>().unwrap_or(0.0);
+        let z = parts[3].parse::<u32>().unwrap_or(0);
+
+        events.push(match mode {
+            GameMode::Std => ReplayEvent::Std(ReplayEventStd {
+                time_delta,
+                x,
+                y,
+                keys: KeyStd(z),
+            }),
+            GameMode::Taiko => ReplayEvent::Taiko(ReplayEventTaiko {
+                time_delta,
+                x,
+                y,
+                keys: KeyTaiko(z),
+            }),
+            GameMode::Catch => ReplayEvent::Catch(ReplayEventCatch {
+                time_delta,
+                x,
+                y,
+                dashing: z & 1 != 0,
+            }),
+            GameMode::Mania => ReplayEvent::Mania(ReplayEventMania {
+                time_delta,
+                keys: KeyMania(x as u32),
+                y,
+            }),
         });
     }
-    Ok(events)
+    Ok((events, rng_seed))
+}
+
+/// Parses the life bar graph: `time|health,` pairs.
+/// Malformed points are skipped, like malformed frames never fail parsing.
+fn parse_life_bar(data: &str) -> Vec<LifeBarPoint> {
+    data.split(',')
+        .filter_map(|point| {
+            let (time, health) = point.split_once('|')?;
+            Some(LifeBarPoint {
+                time_ms: time.trim().parse().ok()?,
+                health: health.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn encode_life_bar(points: &[LifeBarPoint]) -> String {
+    let mut data = String::new();
+    for point in points {
+        data.push_str(&format!("{}|{},", point.time_ms, point.health));
+    }
+    data
 }
 
 fn write_u8(writer: &mut Cursor<Vec<u8>>, val: u8) -> LeptonResult<()> {
@@ -273,22 +388,44 @@ fn write_string(writer: &mut Cursor<Vec<u8>>, val: &str) -> LeptonResult<()> {
     } else {
         write_u8(writer, 0x0b)?;
         let len = val.len() as u64;
-        leb128::write::unsigned(writer, len).map_err(|e| LeptonError::Io(e))?;
+        leb128::write::unsigned(writer, len).map_err(LeptonError::Io)?;
         writer.write_all(val.as_bytes()).map_err(LeptonError::Io)
     }
 }
 
-fn encode_mania_data(events: &[ReplayEventMania]) -> String {
+fn encode_replay_data(events: &[ReplayEvent], rng_seed: Option<i32>) -> String {
     let mut data = String::new();
-    // Default seed
-    data.push_str("-12345|0|0|0,");
 
     for event in events {
         // format: w|x|y|z,
-        // x,y are mouse coordinates, usually 0 or 256 for mania?
-        // standard osu replays have cursor positions. Mania usually has 0?
-        // Let's use 0.
-        data.push_str(&format!("{}|0|0|{},", event.time_delta, event.keys.0));
+        let frame = match event {
+            ReplayEvent::Std(event) => {
+                format!(
+                    "{}|{}|{}|{},",
+                    event.time_delta, event.x, event.y, event.keys.0
+                )
+            }
+            ReplayEvent::Taiko(event) => {
+                format!(
+                    "{}|{}|{}|{},",
+                    event.time_delta, event.x, event.y, event.keys.0
+                )
+            }
+            ReplayEvent::Catch(event) => {
+                format!(
+                    "{}|{}|{}|{},",
+                    event.time_delta, event.x, event.y, event.dashing as u8
+                )
+            }
+            ReplayEvent::Mania(event) => {
+                format!("{}|{}|{}|0,", event.time_delta, event.keys.0, event.y)
+            }
+        };
+        data.push_str(&frame);
+    }
+    // osu! stable writes the seed as the last frame
+    if let Some(seed) = rng_seed {
+        data.push_str(&format!("-12345|0|0|{},", seed));
     }
     data
 }
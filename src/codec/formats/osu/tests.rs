@@ -64,3 +64,739 @@ fn test_mania_key_limit() {
 
     assert_eq!(decoded.inputs[0].column(), 15);
 }
+
+#[test]
+fn test_osu_rate_mods_roundtrip() {
+    let inputs = vec![ReplayInput::new(30000, 0, true, false)];
+
+    for (rate, mods) in [
+        (1.0, OsuMods::NONE),
+        (1.5, OsuMods::DOUBLE_TIME),
+        (0.75, OsuMods::HALF_TIME),
+    ] {
+        let original = ReplayData::with_params(rate, None, inputs.clone());
+        let encoded = OsuEncoder::encode(&original).expect("Encoding failed");
+        assert_eq!(parser::OsuParser::parse(&encoded).unwrap().mods, mods);
+
+        let decoded = OsuDecoder::decode(&encoded).expect("Decoding failed");
+        assert_eq!(decoded.rate, rate);
+        // Deltas are song time and are not rescaled by the rate
+        assert_eq!(decoded.inputs[0].delta_us, 30000);
+    }
+}
+
+#[test]
+fn test_osu_nightcore_rate() {
+    let nightcore = OsuMods::DOUBLE_TIME | OsuMods::NIGHTCORE;
+    assert_eq!(nightcore.rate().unwrap(), 1.5);
+    assert!((OsuMods::DOUBLE_TIME | OsuMods::HALF_TIME).rate().is_err());
+}
+
+#[test]
+fn test_osu_unrepresentable_rate() {
+    // Custom rates need osu!lazer, which stops at 2.0x and 0.5x
+    let original = ReplayData::with_params(2.5, None, vec![]);
+    assert!(OsuEncoder::encode(&original).is_err());
+    let original = ReplayData::with_params(0.25, None, vec![]);
+    assert!(OsuEncoder::encode(&original).is_err());
+}
+
+#[test]
+fn test_osu_mods_acronyms() {
+    let mods: OsuMods = "HDDT".parse().unwrap();
+    assert_eq!(mods, OsuMods::HIDDEN | OsuMods::DOUBLE_TIME);
+    assert_eq!(mods.bits(), 72);
+    assert_eq!(mods.to_string(), "HDDT");
+
+    // NC and PF imply DT and SD
+    let mods: OsuMods = "hd,nc+pf".parse().unwrap();
+    assert!(mods.contains(OsuMods::DOUBLE_TIME | OsuMods::SUDDEN_DEATH));
+    assert_eq!(mods.to_string(), "HDNCPF");
+
+    let mods: OsuMods = "7KMRV2".parse().unwrap();
+    assert_eq!(mods.key_count(), Some(7));
+    assert_eq!(mods.to_string(), "7KV2MR");
+
+    assert_eq!(OsuMods::NONE.to_string(), "NM");
+    assert_eq!("NM".parse::<OsuMods>().unwrap(), OsuMods::NONE);
+    assert!("HDXX".parse::<OsuMods>().is_err());
+    assert!("HDD".parse::<OsuMods>().is_err());
+
+    assert_eq!(
+        OsuMods::from_acronym("nc"),
+        Some(OsuMods::NIGHTCORE | OsuMods::DOUBLE_TIME)
+    );
+    assert_eq!(
+        OsuMods::from_acronym("PF"),
+        Some(OsuMods::PERFECT | OsuMods::SUDDEN_DEATH)
+    );
+    assert_eq!(OsuMods::from_acronym("HD"), Some(OsuMods::HIDDEN));
+    assert_eq!(OsuMods::from_acronym("NM"), None);
+}
+
+#[test]
+fn test_osu_mods_validate() {
+    assert!("HDDTHR".parse::<OsuMods>().unwrap().validate().is_ok());
+    assert!("NCPF".parse::<OsuMods>().unwrap().validate().is_ok());
+
+    for illegal in ["DTHT", "NCHT", "EZHR", "NFSD", "4K7K", "HDFI", "RXAP"] {
+        let mods: OsuMods = illegal.parse().unwrap();
+        assert!(mods.validate().is_err(), "{} should be rejected", illegal);
+    }
+    assert!(OsuMods::NIGHTCORE.validate().is_err());
+    assert!(OsuMods::PERFECT.validate().is_err());
+}
+
+#[test]
+fn test_osu_metadata_roundtrip_through_lep() {
+    use crate::codec::formats::lep::{LepDecoder, LepEncoder};
+    use types::{GameMode, KeyMania, OsuReplay, ReplayEvent, ReplayEventMania};
+
+    let original = OsuReplay {
+        mode: GameMode::Mania,
+        game_version: 20231030,
+        beatmap_hash: "beatmap".to_string(),
+        username: "peppy".to_string(),
+        replay_hash: String::new(),
+        count_300: 400,
+        count_100: 12,
+        count_50: 3,
+        count_geki: 812,
+        count_katu: 25,
+        count_miss: 1,
+        score: 954_321,
+        max_combo: 1024,
+        perfect: false,
+        mods: "HDNC".parse().unwrap(),
+        life_bar_graph: Vec::new(),
+        timestamp: 638_500_000_001_234_567,
+        replay_data: vec![
+            ReplayEvent::Mania(ReplayEventMania {
+                time_delta: 1000,
+                keys: KeyMania(1),
+                y: 0.0,
+            }),
+            ReplayEvent::Mania(ReplayEventMania {
+                time_delta: 80,
+                keys: KeyMania(0),
+                y: 0.0,
+            }),
+        ],
+        replay_id: 4_242_424_242,
+        rng_seed: None,
+        lazer_score_info: None,
+    };
+
+    let osr = parser::OsuParser::encode(&original).unwrap();
+    let replay = OsuDecoder::decode(&osr).unwrap();
+    assert_eq!(replay.metadata.player.as_deref(), Some("peppy"));
+    assert_eq!(replay.metadata.mods, ["HD", "DT", "NC"]);
+    assert_eq!(replay.metadata.score.as_ref().unwrap().count("MAX"), 812);
+
+    let lep = LepEncoder::encode(&replay).unwrap();
+    let replay = LepDecoder::decode(&lep).unwrap();
+    let restored = parser::OsuParser::parse(&OsuEncoder::encode(&replay).unwrap()).unwrap();
+
+    assert_eq!(restored.game_version, original.game_version);
+    assert_eq!(restored.username, original.username);
+    assert_eq!(restored.count_300, original.count_300);
+    assert_eq!(restored.count_100, original.count_100);
+    assert_eq!(restored.count_50, original.count_50);
+    assert_eq!(restored.count_geki, original.count_geki);
+    assert_eq!(restored.count_katu, original.count_katu);
+    assert_eq!(restored.count_miss, original.count_miss);
+    assert_eq!(restored.score, original.score);
+    assert_eq!(restored.max_combo, original.max_combo);
+    assert_eq!(restored.perfect, original.perfect);
+    assert_eq!(restored.mods, original.mods);
+    assert_eq!(restored.timestamp, original.timestamp);
+    assert_eq!(restored.replay_id, original.replay_id);
+}
+
+#[test]
+fn test_osu_malformed_life_bar_points_skipped() {
+    let mut replay = replay_with_frames(types::GameMode::Mania, vec![]);
+    replay.life_bar_graph = vec![
+        types::LifeBarPoint {
+            time_ms: 2436,
+            health: 1.0,
+        },
+        types::LifeBarPoint {
+            time_ms: 4611,
+            health: 0.5,
+        },
+    ];
+    let mut osr = parser::OsuParser::encode(&replay).unwrap();
+
+    // "2436|1,4611|0.5," becomes "2436|1,4611|0.x,"
+    let at = osr.windows(3).position(|bytes| bytes == b"0.5").unwrap();
+    osr[at + 2] = b'x';
+    let parsed = parser::OsuParser::parse(&osr).unwrap();
+    assert_eq!(parsed.life_bar_graph, replay.life_bar_graph[..1]);
+}
+
+#[test]
+fn test_osu_other_game_mods_ignored() {
+    let replay =
+        ReplayData::with_params(1.5, None, vec![]).with_metadata(crate::model::ReplayMetadata {
+            game: Some("Quaver".to_string()),
+            mods: vec!["MR".to_string(), "HD".to_string(), "NF".to_string()],
+            ..Default::default()
+        });
+
+    // Only the rate carries over, as DoubleTime
+    let parsed = parser::OsuParser::parse(&OsuEncoder::encode(&replay).unwrap()).unwrap();
+    assert_eq!(parsed.mods, OsuMods::DOUBLE_TIME);
+}
+
+#[test]
+fn test_osu_encoder_implied_and_invalid_mods() {
+    let replay = |rate: f64, mods: &[&str]| {
+        ReplayData::with_params(rate, None, vec![]).with_metadata(crate::model::ReplayMetadata {
+            game: Some(types::OSU_GAME.to_string()),
+            mods: mods.iter().map(|acronym| acronym.to_string()).collect(),
+            ..Default::default()
+        })
+    };
+
+    // NC and PF recorded alone still write DT and SD
+    let encoded = OsuEncoder::encode(&replay(1.5, &["NC", "PF"])).unwrap();
+    let parsed = parser::OsuParser::parse(&encoded).unwrap();
+    assert_eq!(
+        parsed.mods,
+        OsuMods::NIGHTCORE | OsuMods::DOUBLE_TIME | OsuMods::PERFECT | OsuMods::SUDDEN_DEATH
+    );
+    assert!(parsed.mods.validate().is_ok());
+
+    assert!(OsuEncoder::encode(&replay(1.0, &["EZ", "HR"])).is_err());
+    assert!(OsuEncoder::encode(&replay(1.0, &["NF", "PF"])).is_err());
+}
+
+#[test]
+fn test_osu_life_bar_graph_roundtrip() {
+    use crate::model::HealthPoint;
+
+    let health_graph = vec![
+        HealthPoint {
+            time_us: 2_436_000,
+            health: 1.0,
+        },
+        HealthPoint {
+            time_us: 4_611_000,
+            health: 0.87,
+        },
+    ];
+    let replay = ReplayData::new(vec![ReplayInput::new(1000, 0, true, false)]).with_metadata(
+        crate::model::ReplayMetadata {
+            health_graph: health_graph.clone(),
+            ..Default::default()
+        },
+    );
+
+    let osr = OsuEncoder::encode(&replay).unwrap();
+    let parsed = parser::OsuParser::parse(&osr).unwrap();
+    assert_eq!(
+        parsed.life_bar_graph,
+        vec![
+            types::LifeBarPoint {
+                time_ms: 2436,
+                health: 1.0,
+            },
+            types::LifeBarPoint {
+                time_ms: 4611,
+                health: 0.87,
+            },
+        ]
+    );
+
+    let decoded = OsuDecoder::decode(&osr).unwrap();
+    assert_eq!(decoded.metadata.health_graph, health_graph);
+}
+
+#[test]
+fn test_osu_replay_hash() {
+    let replay = ReplayData::with_params(1.0, Some("beatmap".to_string()), vec![]).with_metadata(
+        crate::model::ReplayMetadata {
+            player: Some("peppy".to_string()),
+            game: Some(types::OSU_GAME.to_string()),
+            score: Some(crate::model::ScoreSummary {
+                score: 1_000_000,
+                max_combo: 10,
+                perfect: true,
+                judgements: vec![crate::model::JudgementCount::new("MAX", 10)],
+            }),
+            ..Default::default()
+        },
+    );
+
+    let mut parsed = parser::OsuParser::parse(&OsuEncoder::encode(&replay).unwrap()).unwrap();
+    // All MAX without mods is an SS ("X")
+    assert_eq!(
+        parsed.replay_hash,
+        format!("{:x}", md5::compute("10osupeppybeatmap1000000X"))
+    );
+    assert!(parser::OsuParser::verify_replay_hash(&parsed));
+
+    parsed.score += 1;
+    assert!(!parser::OsuParser::verify_replay_hash(&parsed));
+}
+
+#[test]
+fn test_osu_grade_per_mode() {
+    use types::GameMode;
+
+    let replay = |mode, [geki, c300, katu, c100, c50, miss]: [u16; 6]| types::OsuReplay {
+        count_geki: geki,
+        count_300: c300,
+        count_katu: katu,
+        count_100: c100,
+        count_50: c50,
+        count_miss: miss,
+        ..replay_with_frames(mode, vec![])
+    };
+
+    // Strong notes (geki) count as extra 300s in the mania formula only
+    let counts = [5, 9, 0, 1, 0, 0];
+    assert_eq!(
+        OsuGrade::from_replay(&replay(GameMode::Mania, counts)),
+        OsuGrade::S
+    );
+    assert_eq!(
+        OsuGrade::from_replay(&replay(GameMode::Taiko, counts)),
+        OsuGrade::A
+    );
+
+    // osu!standard needs no miss and few 50s for an S
+    let std = replay(GameMode::Std, [0, 95, 0, 4, 0, 1]);
+    assert_eq!(OsuGrade::from_replay(&std), OsuGrade::A);
+    let std = replay(GameMode::Std, [0, 95, 0, 5, 0, 0]);
+    assert_eq!(OsuGrade::from_replay(&std), OsuGrade::S);
+
+    // osu!catch: 99 caught out of 100 fruits and droplets
+    let catch = replay(GameMode::Catch, [0, 90, 1, 9, 0, 0]);
+    assert_eq!(OsuGrade::from_replay(&catch), OsuGrade::S);
+
+    // Taiko counts keep their osu! names through the decoder
+    let mut taiko = replay(GameMode::Taiko, counts);
+    taiko.replay_hash = parser::OsuParser::compute_replay_hash(&taiko);
+    let decoded = OsuDecoder::decode(&parser::OsuParser::encode(&taiko).unwrap()).unwrap();
+    let score = decoded.metadata.score.clone().unwrap();
+    assert_eq!(score.count("Geki"), 5);
+    assert_eq!(score.count("MAX"), 0);
+
+    let encoded = OsuEncoder::encode_mode(&decoded, GameMode::Taiko).unwrap();
+    let parsed = parser::OsuParser::parse(&encoded).unwrap();
+    assert_eq!(parsed.count_geki, 5);
+    assert!(parser::OsuParser::verify_replay_hash(&parsed));
+}
+
+fn replay_with_frames(
+    mode: types::GameMode,
+    replay_data: Vec<types::ReplayEvent>,
+) -> types::OsuReplay {
+    types::OsuReplay {
+        mode,
+        game_version: 20240101,
+        beatmap_hash: String::new(),
+        username: String::new(),
+        replay_hash: String::new(),
+        count_300: 0,
+        count_100: 0,
+        count_50: 0,
+        count_geki: 0,
+        count_katu: 0,
+        count_miss: 0,
+        score: 0,
+        max_combo: 0,
+        perfect: false,
+        mods: OsuMods::NONE,
+        life_bar_graph: Vec::new(),
+        timestamp: 0,
+        replay_data,
+        replay_id: 0,
+        rng_seed: None,
+        lazer_score_info: None,
+    }
+}
+
+#[test]
+fn test_osu_frames_roundtrip_all_modes() {
+    use types::*;
+
+    let cases = [
+        (
+            GameMode::Std,
+            vec![
+                ReplayEvent::Std(ReplayEventStd {
+                    time_delta: 16,
+                    x: 256.5,
+                    y: -12.25,
+                    keys: KeyStd(KeyStd::M1.0 | KeyStd::K1.0),
+                }),
+                ReplayEvent::Std(ReplayEventStd {
+                    time_delta: 17,
+                    x: 300.0,
+                    y: 192.0,
+                    keys: KeyStd::SMOKE,
+                }),
+            ],
+        ),
+        (
+            GameMode::Taiko,
+            vec![ReplayEvent::Taiko(ReplayEventTaiko {
+                time_delta: 20,
+                x: 320.0,
+                y: 0.0,
+                keys: KeyTaiko(KeyTaiko::LEFT_DON.0 | KeyTaiko::RIGHT_KAT.0),
+            })],
+        ),
+        (
+            GameMode::Catch,
+            vec![
+                ReplayEvent::Catch(ReplayEventCatch {
+                    time_delta: 8,
+                    x: 123.456,
+                    y: 0.0,
+                    dashing: true,
+                }),
+                ReplayEvent::Catch(ReplayEventCatch {
+                    time_delta: 8,
+                    x: 130.0,
+                    y: 0.0,
+                    dashing: false,
+                }),
+            ],
+        ),
+        (
+            GameMode::Mania,
+            vec![ReplayEvent::Mania(ReplayEventMania {
+                time_delta: 1000,
+                keys: KeyMania(0b101),
+                y: 0.0,
+            })],
+        ),
+    ];
+
+    for (mode, frames) in cases {
+        let replay = replay_with_frames(mode, frames.clone());
+        let parsed =
+            parser::OsuParser::parse(&parser::OsuParser::encode(&replay).unwrap()).unwrap();
+        assert_eq!(parsed.mode, mode);
+        assert_eq!(parsed.replay_data, frames);
+    }
+
+    let catch = replay_with_frames(GameMode::Catch, vec![]);
+    assert!(OsuDecoder::decode(&parser::OsuParser::encode(&catch).unwrap()).is_err());
+}
+
+#[test]
+fn test_osu_taiko_columns() {
+    use types::*;
+
+    let frames = vec![
+        // Left don + right kat
+        ReplayEvent::Taiko(ReplayEventTaiko {
+            time_delta: 1000,
+            x: 320.0,
+            y: 0.0,
+            keys: KeyTaiko(KeyTaiko::LEFT_DON.0 | KeyTaiko::RIGHT_KAT.0),
+        }),
+        // Left kat only
+        ReplayEvent::Taiko(ReplayEventTaiko {
+            time_delta: 50,
+            x: 320.0,
+            y: 0.0,
+            keys: KeyTaiko::LEFT_KAT,
+        }),
+    ];
+    let osr = parser::OsuParser::encode(&replay_with_frames(GameMode::Taiko, frames)).unwrap();
+    let decoded = OsuDecoder::decode(&osr).unwrap();
+
+    let inputs: Vec<(u64, u8, bool)> = decoded
+        .inputs
+        .iter()
+        .map(|input| (input.delta_us, input.column(), input.is_press()))
+        .collect();
+    assert_eq!(
+        inputs,
+        vec![
+            (1_000_000, 1, true),
+            (0, 3, true),
+            (50_000, 0, true),
+            (0, 1, false),
+            (0, 3, false),
+        ]
+    );
+
+    // And back to taiko frames
+    let encoded = OsuEncoder::encode_mode(&decoded, GameMode::Taiko).unwrap();
+    let parsed = parser::OsuParser::parse(&encoded).unwrap();
+    assert_eq!(parsed.mode, GameMode::Taiko);
+    let keys: Vec<KeyTaiko> = parsed
+        .replay_data
+        .iter()
+        .filter_map(|event| match event {
+            ReplayEvent::Taiko(event) => Some(event.keys),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(keys.first(), Some(&KeyTaiko::LEFT_DON));
+    assert_eq!(keys.last(), Some(&KeyTaiko::LEFT_KAT));
+
+    let too_wide = ReplayData::new(vec![ReplayInput::new(0, 4, true, false)]);
+    assert!(OsuEncoder::encode_mode(&too_wide, GameMode::Taiko).is_err());
+}
+
+#[test]
+fn test_osu_taiko_convert_keeps_mode() {
+    use crate::codec::formats::lep::{LepDecoder, LepEncoder};
+    use crate::codec::traits::convert;
+    use types::*;
+
+    let frames = vec![
+        ReplayEvent::Taiko(ReplayEventTaiko {
+            time_delta: 1000,
+            x: 320.0,
+            y: 0.0,
+            keys: KeyTaiko::RIGHT_DON,
+        }),
+        ReplayEvent::Taiko(ReplayEventTaiko {
+            time_delta: 50,
+            x: 320.0,
+            y: 0.0,
+            keys: KeyTaiko(0),
+        }),
+    ];
+    let osr = parser::OsuParser::encode(&replay_with_frames(GameMode::Taiko, frames)).unwrap();
+
+    let converted = convert::<OsuDecoder, OsuEncoder>(&osr).unwrap();
+    let parsed = parser::OsuParser::parse(&converted).unwrap();
+    assert_eq!(parsed.mode, GameMode::Taiko);
+    assert!(parsed.replay_data.iter().any(|event| matches!(
+        event,
+        ReplayEvent::Taiko(event) if event.keys == KeyTaiko::RIGHT_DON
+    )));
+
+    // The mode also survives a trip through LEP
+    let lep = convert::<OsuDecoder, LepEncoder>(&osr).unwrap();
+    let converted = convert::<LepDecoder, OsuEncoder>(&lep).unwrap();
+    assert_eq!(
+        parser::OsuParser::parse(&converted).unwrap().mode,
+        GameMode::Taiko
+    );
+
+    // Replays from other formats stay osu!mania
+    let other = ReplayData::new(vec![ReplayInput::new(0, 0, true, false)]);
+    let parsed = parser::OsuParser::parse(&OsuEncoder::encode(&other).unwrap()).unwrap();
+    assert_eq!(parsed.mode, GameMode::Mania);
+}
+
+#[test]
+fn test_osu_lazer_score_info_roundtrip() {
+    use lazer::{LAZER_FIRST_VERSION, LazerMod};
+
+    let mut settings = serde_json::Map::new();
+    settings.insert("speed_change".to_string(), serde_json::Value::from(1.3));
+    settings.insert("adjust_pitch".to_string(), serde_json::Value::from(true));
+    let mut extra = serde_json::Map::new();
+    extra.insert("user_id".to_string(), serde_json::Value::from(2));
+    let info = LazerScoreInfo {
+        online_id: 42,
+        mods: vec![
+            LazerMod {
+                acronym: "DT".to_string(),
+                settings,
+            },
+            LazerMod {
+                acronym: "HD".to_string(),
+                settings: Default::default(),
+            },
+        ],
+        statistics: [("great".to_string(), 100), ("miss".to_string(), 2)].into(),
+        maximum_statistics: [("perfect".to_string(), 102)].into(),
+        client_version: Some("2024.1009.1".to_string()),
+        extra,
+    };
+
+    let mut replay = replay_with_frames(
+        types::GameMode::Mania,
+        vec![types::ReplayEvent::Mania(types::ReplayEventMania {
+            time_delta: 500,
+            keys: types::KeyMania(1),
+            y: 0.0,
+        })],
+    );
+    replay.game_version = LAZER_FIRST_VERSION;
+    replay.mods = OsuMods::DOUBLE_TIME | OsuMods::HIDDEN;
+    replay.lazer_score_info = Some(info.clone());
+
+    let osr = parser::OsuParser::encode(&replay).unwrap();
+    let parsed = parser::OsuParser::parse(&osr).unwrap();
+    assert_eq!(parsed.lazer_score_info, Some(info));
+
+    let decoded = OsuDecoder::decode(&osr).unwrap();
+    assert_eq!(decoded.rate, 1.3);
+}
+
+#[test]
+fn test_osu_encode_custom_rate() {
+    let replay = ReplayData::with_params(0.8, None, vec![ReplayInput::new(1000, 0, true, false)]);
+    let osr = OsuEncoder::encode(&replay).unwrap();
+
+    let parsed = parser::OsuParser::parse(&osr).unwrap();
+    assert!(parsed.mods.contains(OsuMods::HALF_TIME));
+    assert!(parsed.game_version >= lazer::LAZER_FIRST_VERSION);
+    let info = parsed.lazer_score_info.unwrap();
+    assert_eq!(info.mods[0].acronym, "HT");
+    assert_eq!(info.rate(), 0.8);
+
+    assert_eq!(OsuDecoder::decode(&osr).unwrap().rate, 0.8);
+}
+
+#[test]
+fn test_osu_encode_no_timing_drift() {
+    // Sub-millisecond deltas over a long replay: rounding each delta on its own
+    // would drift by hundreds of ms, rounding absolute time never does.
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut pressed = [false; 4];
+    let inputs: Vec<ReplayInput> = (0..5000)
+        .map(|_| {
+            // xorshift64
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let column = (seed % 4) as usize;
+            pressed[column] = !pressed[column];
+            ReplayInput::new(seed % 80_000 + 1, column as u8, pressed[column], false)
+        })
+        .collect();
+
+    let original = ReplayData::with_params(1.0, None, inputs);
+    let encoded = OsuEncoder::encode(&original).unwrap();
+    let decoded = OsuDecoder::decode(&encoded).unwrap();
+    assert_eq!(decoded.inputs.len(), original.inputs.len());
+
+    let mut original_us = 0i64;
+    let mut decoded_us = 0i64;
+    for (orig, dec) in original.inputs.iter().zip(&decoded.inputs) {
+        original_us += orig.delta_us as i64;
+        decoded_us += dec.delta_us as i64;
+        assert!((original_us - decoded_us).abs() <= 500);
+        assert_eq!(orig.column(), dec.column());
+        assert_eq!(orig.is_press(), dec.is_press());
+    }
+}
+
+#[test]
+fn test_osu_seed_and_skip_frames() {
+    use types::{KeyMania, KeyStd, ReplayEvent, ReplayEventMania, ReplayEventStd};
+
+    let std_frame = |time_delta, x, y| {
+        ReplayEvent::Std(ReplayEventStd {
+            time_delta,
+            x,
+            y,
+            keys: KeyStd(0),
+        })
+    };
+    // Stable starts replays with two 256|-500 frames at 0 and skip boundary - 1
+    let frames = vec![
+        std_frame(0, 256.0, -500.0),
+        std_frame(-1, 256.0, -500.0),
+        std_frame(20, 100.0, 100.0),
+        std_frame(15_000, 120.0, 100.0),
+    ];
+    let mut replay = replay_with_frames(types::GameMode::Std, frames.clone());
+    replay.rng_seed = Some(-42);
+
+    // The raw layer keeps every frame
+    let osr = parser::OsuParser::encode(&replay).unwrap();
+    let parsed = parser::OsuParser::parse(&osr).unwrap();
+    assert_eq!(parsed.rng_seed, Some(-42));
+    assert_eq!(parsed.replay_data, frames);
+    assert_eq!(parser::OsuParser::encode(&parsed).unwrap(), osr);
+
+    // The decoder does not play them, but their delta still moves time
+    let mania_frame = |time_delta, keys, y| {
+        ReplayEvent::Mania(ReplayEventMania {
+            time_delta,
+            keys: KeyMania(keys),
+            y,
+        })
+    };
+    let replay = replay_with_frames(
+        types::GameMode::Mania,
+        vec![
+            mania_frame(0, 256, -500.0),
+            mania_frame(-1, 256, -500.0),
+            mania_frame(21, 1, 0.0),
+            mania_frame(10, 0, 0.0),
+        ],
+    );
+    let osr = parser::OsuParser::encode(&replay).unwrap();
+    assert_eq!(parser::OsuParser::parse(&osr).unwrap().replay_data.len(), 4);
+    let decoded = OsuDecoder::decode(&osr).unwrap();
+    assert_eq!(
+        decoded.inputs,
+        [
+            ReplayInput::new(20_000, 0, true, false),
+            ReplayInput::new(10_000, 0, false, false),
+        ]
+    );
+}
+
+#[test]
+fn test_osu_negative_frames_and_start_offset() {
+    use types::{KeyMania, ReplayEvent, ReplayEventMania};
+
+    let mania_frame = |time_delta, keys| {
+        ReplayEvent::Mania(ReplayEventMania {
+            time_delta,
+            keys: KeyMania(keys),
+            y: 0.0,
+        })
+    };
+    let replay = replay_with_frames(
+        types::GameMode::Mania,
+        vec![
+            // Lead-in: first press 1.5s before the chart starts
+            mania_frame(-2000, 0),
+            mania_frame(500, 1),
+            mania_frame(100, 0),
+            // Negative frames move time back but are not played
+            mania_frame(-50, 2),
+            mania_frame(1000, 2),
+            mania_frame(10, 0),
+        ],
+    );
+    let osr = parser::OsuParser::encode(&replay).unwrap();
+    let decoded = OsuDecoder::decode(&osr).unwrap();
+
+    assert_eq!(decoded.start_offset_us, -1_500_000);
+    let times: Vec<(i64, u8, bool)> = decoded
+        .inputs
+        .iter()
+        .scan(decoded.start_offset_us, |time_us, input| {
+            *time_us += input.delta_us as i64;
+            Some((*time_us, input.column(), input.is_press()))
+        })
+        .collect();
+    assert_eq!(
+        times,
+        [
+            (-1_500_000, 0, true),
+            (-1_400_000, 0, false),
+            (-450_000, 1, true),
+            (-440_000, 1, false),
+        ]
+    );
+
+    // The encoder reaches the negative start with a frame osu! does not play
+    let reencoded = OsuEncoder::encode(&decoded).unwrap();
+    let parsed = parser::OsuParser::parse(&reencoded).unwrap();
+    assert_eq!(parsed.rng_seed, Some(0));
+    assert_eq!(parsed.replay_data[0], mania_frame(-1500, 0));
+    let redecoded = OsuDecoder::decode(&reencoded).unwrap();
+    assert_eq!(redecoded.start_offset_us, decoded.start_offset_us);
+    assert_eq!(redecoded.inputs, decoded.inputs);
+}
@@ -2,6 +2,44 @@
 //!
 //! Adapted from rosu-replay.
 
+use super::lazer::LazerScoreInfo;
+use super::mods::OsuMods;
+
+/// Game name recorded in `ReplayMetadata::game` for osu! replays.
+pub const OSU_GAME: &str = "osu!";
+
+/// Name of the `ReplayData::extensions` entry holding the osu! mode of a
+/// decoded replay, as one `GameMode` byte.
+pub const OSU_MODE_EXTENSION: &str = "osu!mode";
+
+/// Judgement names used in `ScoreSummary` for osu!mania replays, in the
+/// order geki, 300, katu, 100, 50, miss.
+pub const OSU_JUDGEMENT_NAMES: [&str; 6] = ["MAX", "300", "200", "100", "50", "Miss"];
+
+/// Names of the `count_*` fields used in `ScoreSummary` for the other osu!
+/// modes, where geki and katu are not plain judgements, in the same order.
+pub const OSU_COUNT_NAMES: [&str; 6] = ["Geki", "300", "Katu", "100", "50", "Miss"];
+
+/// .NET ticks (100ns since 0001-01-01) at the Unix epoch.
+const UNIX_EPOCH_TICKS: i64 = 621_355_968_000_000_000;
+
+/// Converts a replay timestamp (.NET ticks) to nanoseconds since the Unix epoch.
+/// Returns `None` for 0 (unset) or out of range timestamps.
+pub fn ticks_to_unix_ns(ticks: u64) -> Option<i64> {
+    if ticks == 0 {
+        return None;
+    }
+    i64::try_from(ticks)
+        .ok()?
+        .checked_sub(UNIX_EPOCH_TICKS)?
+        .checked_mul(100)
+}
+
+/// Converts nanoseconds since the Unix epoch to a replay timestamp (.NET ticks).
+pub fn unix_ns_to_ticks(unix_ns: i64) -> u64 {
+    (unix_ns.div_euclid(100) + UNIX_EPOCH_TICKS).max(0) as u64
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum GameMode {
@@ -11,6 +49,18 @@ pub enum GameMode {
     Mania = 3,
 }
 
+impl GameMode {
+    /// Judgement names used in `ScoreSummary` for the `count_*` fields of a
+    /// replay of this mode.
+    #[must_use]
+    pub fn judgement_names(self) -> [&'static str; 6] {
+        match self {
+            GameMode::Mania => OSU_JUDGEMENT_NAMES,
+            _ => OSU_COUNT_NAMES,
+        }
+    }
+}
+
 impl From<u8> for GameMode {
     fn from(value: u8) -> Self {
         match value {
@@ -45,6 +95,150 @@ impl From<u32> for KeyMania {
 pub struct ReplayEventMania {
     pub time_delta: i32,
     pub keys: KeyMania,
+
+    /// Unused cursor height, kept for lossless re-encoding.
+    pub y: f32,
+}
+
+/// Keys and mouse buttons of an osu!standard frame.
+///
+/// K1 is always stored together with M1, and K2 with M2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyStd(pub u32);
+
+impl KeyStd {
+    pub const M1: Self = Self(1 << 0);
+    pub const M2: Self = Self(1 << 1);
+    pub const K1: Self = Self(1 << 2);
+    pub const K2: Self = Self(1 << 3);
+    pub const SMOKE: Self = Self(1 << 4);
+
+    /// Checks if every key of `other` is pressed.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayEventStd {
+    pub time_delta: i32,
+
+    /// Cursor position in osu!pixels.
+    pub x: f32,
+    pub y: f32,
+
+    pub keys: KeyStd,
+}
+
+/// Drum keys of an osu!taiko frame, stored in the M1/M2/K1/K2 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyTaiko(pub u32);
+
+impl KeyTaiko {
+    pub const LEFT_DON: Self = Self(1 << 0);
+    pub const LEFT_KAT: Self = Self(1 << 1);
+    pub const RIGHT_DON: Self = Self(1 << 2);
+    pub const RIGHT_KAT: Self = Self(1 << 3);
+
+    /// Drum key of each Lepton column when a taiko replay is mapped to 4 keys:
+    /// left kat, left don, right don, right kat.
+    pub const COLUMNS: [Self; 4] = [
+        Self::LEFT_KAT,
+        Self::LEFT_DON,
+        Self::RIGHT_DON,
+        Self::RIGHT_KAT,
+    ];
+
+    /// Checks if every key of `other` is pressed.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Converts the pressed drum keys to a column bitfield (see `COLUMNS`).
+    pub fn to_columns(self) -> u32 {
+        Self::COLUMNS
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| self.contains(**key))
+            .fold(0, |columns, (col, _)| columns | (1 << col))
+    }
+
+    /// Converts a column bitfield (see `COLUMNS`) to drum keys.
+    /// Columns above 3 are ignored.
+    pub fn from_columns(columns: u32) -> Self {
+        Self::COLUMNS
+            .iter()
+            .enumerate()
+            .filter(|(col, _)| columns & (1 << col) != 0)
+            .fold(Self(0), |keys, (_, key)| Self(keys.0 | key.0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayEventTaiko {
+    pub time_delta: i32,
+
+    /// Unused cursor position, kept for lossless re-encoding.
+    pub x: f32,
+    pub y: f32,
+
+    pub keys: KeyTaiko,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayEventCatch {
+    pub time_delta: i32,
+
+    /// Catcher position in osu!pixels.
+    pub x: f32,
+
+    /// Unused cursor height, kept for lossless re-encoding.
+    pub y: f32,
+
+    pub dashing: bool,
+}
+
+/// A replay frame of any ruleset.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayEvent {
+    Std(ReplayEventStd),
+    Taiko(ReplayEventTaiko),
+    Catch(ReplayEventCatch),
+    Mania(ReplayEventMania),
+}
+
+impl ReplayEvent {
+    /// Returns the time since the previous frame in milliseconds.
+    pub fn time_delta(&self) -> i32 {
+        match self {
+            ReplayEvent::Std(event) => event.time_delta,
+            ReplayEvent::Taiko(event) => event.time_delta,
+            ReplayEvent::Catch(event) => event.time_delta,
+            ReplayEvent::Mania(event) => event.time_delta,
+        }
+    }
+
+    /// Checks if this is one of the `256|-500` frames osu! stable writes at
+    /// the start of a replay, which the client does not play.
+    pub fn is_skip_frame(&self) -> bool {
+        let (x, y) = match self {
+            ReplayEvent::Std(event) => (event.x, event.y),
+            ReplayEvent::Taiko(event) => (event.x, event.y),
+            ReplayEvent::Catch(event) => (event.x, event.y),
+            ReplayEvent::Mania(event) => (event.keys.0 as f32, event.y),
+        };
+        x == 256.0 && y == -500.0
+    }
+}
+
+/// A point of the life bar graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LifeBarPoint {
+    /// Time in milliseconds into the song.
+    pub time_ms: i32,
+
+    /// Health from 0.0 (empty) to 1.0 (full).
+    pub health: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -63,9 +257,16 @@ pub struct OsuReplay {
     pub score: u32,
     pub max_combo: u16,
     pub perfect: bool,
-    pub mods: u32,
-    pub life_bar_graph: String, // Kept as string for simplicity unless needed
-    pub timestamp: u64,         // Windows ticks
-    pub replay_data: Vec<ReplayEventMania>,
+    pub mods: OsuMods,
+    pub life_bar_graph: Vec<LifeBarPoint>,
+    pub timestamp: u64, // Windows ticks
+    pub replay_data: Vec<ReplayEvent>,
+
+    /// RNG seed from the `-12345` frame, if the replay has one.
+    pub rng_seed: Option<i32>,
+
     pub replay_id: i64,
+
+    /// Score info block appended by osu!lazer.
+    pub lazer_score_info: Option<LazerScoreInfo>,
 }
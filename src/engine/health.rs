@@ -0,0 +1,151 @@
+//! osu!mania health (HP) simulation.
+//!
+//! Replays the health changes of every judgement to rebuild a life bar graph,
+//! e.g. when writing an `.osr` from a replay that never had one.
+
+use crate::engine::simulator::NoteJudgement;
+use crate::engine::timing::TailJudgement;
+use crate::engine::timing::hit_windows::OsuJudgement;
+use crate::model::{Chart, HealthPoint};
+
+/// Interval between two points of the generated life bar graph.
+pub const OSU_LIFE_BAR_INTERVAL_US: i64 = 2_000_000;
+
+/// Returns the health change of a judgement for an HP drain rate (0-10).
+///
+/// Long note heads and tails (`partial`) are worth half a note. Mirrors the
+/// osu!mania health processor: no passive drain, stricter HP gives less
+/// health back and takes more away.
+#[must_use]
+pub fn osu_health_change(judgement: OsuJudgement, hp_drain_rate: f32, partial: bool) -> f64 {
+    let hp = hp_drain_rate as f64;
+    let change = match judgement {
+        OsuJudgement::Marvelous => 0.0055 - hp * 0.0005,
+        OsuJudgement::Perfect => 0.005 - hp * 0.0005,
+        OsuJudgement::Great => 0.004 - hp * 0.0004,
+        OsuJudgement::Good => 0.0,
+        OsuJudgement::Bad => -(hp + 1.0) * 0.0016,
+        OsuJudgement::Miss => -(hp + 1.0) * 0.0075,
+    };
+
+    if partial { change / 2.0 } else { change }
+}
+
+/// Simulates health over a play and samples it every `interval_us`.
+///
+/// Health starts full and each judgement applies at the time it was given.
+/// The graph ends with a point at the last judgement.
+#[must_use]
+pub fn osu_health_graph(
+    chart: &Chart,
+    results: &[NoteJudgement<OsuJudgement>],
+    hp_drain_rate: f32,
+    interval_us: i64,
+) -> Vec<HealthPoint> {
+    let mut events: Vec<(i64, f64)> = Vec::with_capacity(results.len());
+    for result in results {
+        let note = &chart.notes[result.note_index];
+        let partial = note.is_hold();
+
+        events.push((
+            note.time_us + result.delta_us.unwrap_or(0),
+            osu_health_change(result.judgement, hp_drain_rate, partial),
+        ));
+
+        let tail = match result.tail {
+            Some(TailJudgement::Release {
+                judgement,
+                delta_us,
+            }) => Some((note.end_us() + delta_us.unwrap_or(0), judgement)),
+            Some(TailJudgement::Dropped) => Some((note.end_us(), OsuJudgement::Miss)),
+            Some(TailJudgement::Held) | None => None,
+        };
+        if let Some((time_us, judgement)) = tail {
+            events.push((time_us, osu_health_change(judgement, hp_drain_rate, true)));
+        }
+    }
+    events.sort_by_key(|&(time_us, _)| time_us);
+
+    let Some(&(end_us, _)) = events.last() else {
+        return Vec::new();
+    };
+
+    let mut graph = Vec::new();
+    let mut health = 1.0;
+    let mut next = 0;
+    let mut sample_us = interval_us.max(1);
+
+    loop {
+        let time_us = sample_us.min(end_us);
+        while next < events.len() && events[next].0 <= time_us {
+            health = (health + events[next].1).clamp(0.0, 1.0);
+            next += 1;
+        }
+        graph.push(HealthPoint { time_us, health });
+
+        if time_us == end_us {
+            return graph;
+        }
+        sample_us += interval_us.max(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::simulator::simulate_holds;
+    use crate::engine::timing::hit_windows::osu::{create_osu_tail_rule, create_osu_windows};
+    use crate::model::{Note, ReplayData, ReplayInput};
+
+    #[test]
+    fn test_osu_health_change() {
+        assert!(osu_health_change(OsuJudgement::Marvelous, 8.0, false) > 0.0);
+        assert_eq!(osu_health_change(OsuJudgement::Good, 8.0, false), 0.0);
+        assert_eq!(osu_health_change(OsuJudgement::Miss, 7.0, false), -0.06);
+        assert_eq!(osu_health_change(OsuJudgement::Miss, 7.0, true), -0.03);
+
+        // Higher HP is harsher
+        assert!(
+            osu_health_change(OsuJudgement::Miss, 9.0, false)
+                < osu_health_change(OsuJudgement::Miss, 2.0, false)
+        );
+    }
+
+    #[test]
+    fn test_osu_health_graph() {
+        let chart = Chart::new(
+            4,
+            vec![
+                Note::tap(1_000_000, 0),
+                Note::tap(3_000_000, 1),
+                Note::hold(5_000_000, 6_000_000, 2),
+            ],
+        );
+        // Hit the first tap, miss everything else.
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(1_000_000, 0, true, false),
+            ReplayInput::new(50_000, 0, false, false),
+        ]);
+        let results = simulate_holds(
+            &replay,
+            &chart,
+            &create_osu_windows(8.0),
+            &create_osu_tail_rule(8.0),
+        );
+
+        let graph = osu_health_graph(&chart, &results, 7.0, OSU_LIFE_BAR_INTERVAL_US);
+        let times: Vec<i64> = graph.iter().map(|point| point.time_us).collect();
+        assert!(times.starts_with(&[2_000_000, 4_000_000, 6_000_000]));
+
+        // Full, then a missed tap, then a missed head and tail.
+        assert_eq!(graph[0].health, 1.0);
+        assert!((graph[1].health - 0.94).abs() < 1e-9);
+        assert!(graph.last().unwrap().health < graph[1].health);
+    }
+
+    #[test]
+    fn test_osu_health_graph_empty() {
+        let chart = Chart::new(4, vec![]);
+        assert!(osu_health_graph(&chart, &[], 8.0, OSU_LIFE_BAR_INTERVAL_US).is_empty());
+    }
+}
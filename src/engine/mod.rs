@@ -1 +1,9 @@
+pub mod health;
+pub mod rescore;
+pub mod scoring;
+pub mod simulator;
 pub mod timing;
+
+pub use health::{osu_health_change, osu_health_graph};
+pub use rescore::{PlaySummary, RescoreReport, rescore, rescore_holds};
+pub use simulator::{NoteJudgement, mine_hits, simulate, simulate_holds};
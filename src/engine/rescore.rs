@@ -0,0 +1,386 @@
+//! Rescoring of a replay under different hit windows.
+//!
+//! Answers "what would this play be on J7 / OD 9?" by judging the same
+//! replay twice and comparing judgement counts, accuracy and combo.
+
+use crate::engine::scoring::etterna::EtternaPlay;
+use crate::engine::scoring::{ScoredJudgement, accuracy, judgement_sequence, max_combo};
+use crate::engine::simulator::{NoteJudgement, mine_hits, simulate, simulate_holds};
+use crate::engine::timing::HitWindows;
+use crate::engine::timing::hit_windows::etterna::{
+    ETTERNA_MINE_WINDOW_US, EtternaJudgement, create_etterna_tail_rule, create_etterna_windows,
+};
+use crate::engine::timing::hit_windows::osu::{
+    OsuJudgement, OsuRulesetVariant, create_osu_tail_rule_variant, create_osu_windows_variant,
+};
+use crate::engine::timing::hold::TailRule;
+use crate::model::{Chart, ReplayData};
+
+/// Judgement counts, accuracy and combo of a play under one set of windows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaySummary<J> {
+    /// Number of each judgement, best (highest accuracy weight) first.
+    pub counts: Vec<(J, u32)>,
+
+    /// Mean accuracy weight, up to 1.0. Negative weights (Etterna, Quaver,
+    /// ITG) and Wife3 can bring it below zero.
+    pub accuracy: f64,
+
+    pub max_combo: u32,
+
+    /// Per-note simulator results.
+    pub results: Vec<NoteJudgement<J>>,
+}
+
+impl<J: ScoredJudgement> PlaySummary<J> {
+    /// Summarises a chronological judgement sequence.
+    #[must_use]
+    pub fn new(judgements: &[J], results: Vec<NoteJudgement<J>>) -> Self {
+        let mut counts: Vec<(J, u32)> = Vec::new();
+        for judgement in judgements {
+            match counts.iter_mut().find(|(j, _)| j == judgement) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((*judgement, 1)),
+            }
+        }
+        counts.sort_by(|(a, _), (b, _)| b.accuracy_weight().total_cmp(&a.accuracy_weight()));
+
+        Self {
+            counts,
+            accuracy: accuracy(judgements),
+            max_combo: max_combo(judgements),
+            results,
+        }
+    }
+
+    /// Returns the number of times `judgement` was given.
+    #[must_use]
+    pub fn count(&self, judgement: J) -> u32 {
+        self.counts
+            .iter()
+            .find(|(j, _)| *j == judgement)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+/// Comparison of a play under its original and a target set of windows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RescoreReport<J> {
+    pub original: PlaySummary<J>,
+    pub target: PlaySummary<J>,
+}
+
+impl<J: ScoredJudgement> RescoreReport<J> {
+    /// Accuracy change from original to target.
+    #[must_use]
+    pub fn accuracy_delta(&self) -> f64 {
+        self.target.accuracy - self.original.accuracy
+    }
+
+    /// Max combo change from original to target.
+    #[must_use]
+    pub fn combo_delta(&self) -> i64 {
+        self.target.max_combo as i64 - self.original.max_combo as i64
+    }
+
+    /// Change in the number of times `judgement` was given.
+    #[must_use]
+    pub fn count_delta(&self, judgement: J) -> i64 {
+        self.target.count(judgement) as i64 - self.original.count(judgement) as i64
+    }
+}
+
+/// Judges a replay under two sets of hit windows and compares the results.
+///
+/// Windows are in real time: offsets of rated replays are divided by
+/// `ReplayData::rate` before judging.
+///
+/// Notes are judged with [`simulate`], so long notes count on their head only.
+/// Use [`rescore_holds`] to judge long note tails as well.
+#[must_use]
+pub fn rescore<W, T>(
+    replay: &ReplayData,
+    chart: &Chart,
+    original: &W,
+    target: &T,
+) -> RescoreReport<W::Judgement>
+where
+    W: HitWindows,
+    W::Judgement: ScoredJudgement,
+    T: HitWindows<Judgement = W::Judgement>,
+{
+    let summarise = |results: Vec<NoteJudgement<W::Judgement>>| {
+        let judgements = judgement_sequence(chart, &results);
+        PlaySummary::new(&judgements, results)
+    };
+
+    RescoreReport {
+        original: summarise(simulate(replay, chart, original)),
+        target: summarise(simulate(replay, chart, target)),
+    }
+}
+
+/// Judges a replay under two sets of hit windows and long note tail rules and
+/// compares the results.
+///
+/// Notes are judged with [`simulate_holds`], so long note tails count as well.
+#[must_use]
+pub fn rescore_holds<W, R, T, S>(
+    replay: &ReplayData,
+    chart: &Chart,
+    (original, original_tail): (&W, &TailRule<R>),
+    (target, target_tail): (&T, &TailRule<S>),
+) -> RescoreReport<W::Judgement>
+where
+    W: HitWindows,
+    W::Judgement: ScoredJudgement,
+    R: HitWindows<Judgement = W::Judgement>,
+    T: HitWindows<Judgement = W::Judgement>,
+    S: HitWindows<Judgement = W::Judgement>,
+{
+    let summarise = |results: Vec<NoteJudgement<W::Judgement>>| {
+        let judgements = judgement_sequence(chart, &results);
+        PlaySummary::new(&judgements, results)
+    };
+
+    RescoreReport {
+        original: summarise(simulate_holds(replay, chart, original, original_tail)),
+        target: summarise(simulate_holds(replay, chart, target, target_tail)),
+    }
+}
+
+/// Rescores an osu!mania replay from one Overall Difficulty to another, as
+/// judged by osu! stable on a mania-specific beatmap without mods.
+///
+/// Long note heads and tails are both judged, with stable release windows.
+#[must_use]
+pub fn rescore_osu(
+    replay: &ReplayData,
+    chart: &Chart,
+    original_od: f32,
+    target_od: f32,
+) -> RescoreReport<OsuJudgement> {
+    rescore_osu_variant(
+        replay,
+        chart,
+        original_od,
+        target_od,
+        OsuRulesetVariant::STABLE,
+    )
+}
+
+/// Rescores an osu!mania replay from one Overall Difficulty to another for a
+/// ruleset variant (client, convert, difficulty mod).
+///
+/// Long note heads and tails are both judged, with the release windows of
+/// the variant.
+#[must_use]
+pub fn rescore_osu_variant(
+    replay: &ReplayData,
+    chart: &Chart,
+    original_od: f32,
+    target_od: f32,
+    variant: OsuRulesetVariant,
+) -> RescoreReport<OsuJudgement> {
+    let windows = |od: f32| {
+        (
+            create_osu_windows_variant(od, variant),
+            create_osu_tail_rule_variant(od, variant),
+        )
+    };
+    let (original, original_tail) = windows(original_od);
+    let (target, target_tail) = windows(target_od);
+
+    rescore_holds(
+        replay,
+        chart,
+        (&original, &original_tail),
+        (&target, &target_tail),
+    )
+}
+
+/// Rescores an Etterna replay from one judge level to another.
+///
+/// Accuracy is the Wife3 score of each judge level as a fraction (1.0 is
+/// 100%, and it can go negative), including mine hits and hold drops.
+#[must_use]
+pub fn rescore_etterna(
+    replay: &ReplayData,
+    chart: &Chart,
+    original_judge: i64,
+    target_judge: i64,
+) -> RescoreReport<EtternaJudgement> {
+    let mines = mine_hits(replay, chart, ETTERNA_MINE_WINDOW_US).len() as u32;
+    let summarise = |judge_level: i64| {
+        let results = simulate_holds(
+            replay,
+            chart,
+            &create_etterna_windows(judge_level),
+            &create_etterna_tail_rule(),
+        );
+        let judgements = judgement_sequence(chart, &results);
+        let wife3 = EtternaPlay::from_results(&results, mines).wife3(judge_level);
+        PlaySummary {
+            accuracy: wife3 / 100.0,
+            ..PlaySummary::new(&judgements, results)
+        }
+    };
+
+    RescoreReport {
+        original: summarise(original_judge),
+        target: summarise(target_judge),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::timing::hit_windows::osu::{
+        OsuDifficultyMod, create_osu_tail_rule, create_osu_windows,
+    };
+    use crate::model::{Note, ReplayInput};
+
+    fn chart_and_replay() -> (Chart, ReplayData) {
+        let chart = Chart::new(
+            4,
+            vec![
+                Note::tap(1_000_000, 0),
+                Note::tap(2_000_000, 1),
+                Note::tap(3_000_000, 2),
+            ],
+        );
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(1_000_000, 0, true, false), // 0ms
+            ReplayInput::new(1_020_000, 1, true, false), // +20ms
+            ReplayInput::new(1_040_000, 2, true, false), // +60ms
+        ]);
+        (chart, replay)
+    }
+
+    #[test]
+    fn test_rescore_same_windows() {
+        let (chart, replay) = chart_and_replay();
+        let windows = create_osu_windows(8.0);
+
+        let report = rescore(&replay, &chart, &windows, &windows);
+
+        assert_eq!(report.original, report.target);
+        assert_eq!(report.accuracy_delta(), 0.0);
+        assert_eq!(report.combo_delta(), 0);
+    }
+
+    #[test]
+    fn test_rescore_holds_judges_tails() {
+        let chart = Chart::new(4, vec![Note::hold(1_000_000, 2_000_000, 0)]);
+        // Released 500ms before the tail
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(1_000_000, 0, true, false),
+            ReplayInput::new(500_000, 0, false, false),
+        ]);
+        let windows = create_osu_windows(8.0);
+        let tail_rule = create_osu_tail_rule(8.0);
+
+        let heads = rescore(&replay, &chart, &windows, &windows);
+        assert_eq!(heads.target.count(OsuJudgement::Miss), 0);
+
+        let report = rescore_holds(
+            &replay,
+            &chart,
+            (&windows, &tail_rule),
+            (&windows, &tail_rule),
+        );
+        assert_eq!(report.target.count(OsuJudgement::Marvelous), 1);
+        assert_eq!(report.target.count(OsuJudgement::Miss), 1);
+        assert_eq!(report.original, report.target);
+    }
+
+    #[test]
+    fn test_rescore_osu_stricter_od() {
+        let (chart, replay) = chart_and_replay();
+
+        let report = rescore_osu(&replay, &chart, 0.0, 10.0);
+
+        // OD0: 20ms is a 300 and 60ms is a 300.
+        assert_eq!(report.original.count(OsuJudgement::Marvelous), 1);
+        assert_eq!(report.original.count(OsuJudgement::Perfect), 2);
+        // OD10: 20ms is a 300 and 60ms is a 200.
+        assert_eq!(report.target.count(OsuJudgement::Perfect), 1);
+        assert_eq!(report.target.count(OsuJudgement::Great), 1);
+        assert_eq!(report.count_delta(OsuJudgement::Great), 1);
+        assert!(report.accuracy_delta() < 0.0);
+        assert_eq!(report.target.max_combo, 3);
+        assert_eq!(report.target.counts[0].0, OsuJudgement::Marvelous);
+    }
+
+    #[test]
+    fn test_rescore_osu_variant() {
+        let (chart, replay) = chart_and_replay();
+        let stable = rescore_osu(&replay, &chart, 0.0, 10.0);
+        assert_eq!(
+            rescore_osu_variant(&replay, &chart, 0.0, 10.0, OsuRulesetVariant::STABLE),
+            stable
+        );
+
+        // HR on stable at OD10: 300 = floor(34 / 1.4) = 24ms and
+        // 100 = floor(97 / 1.4) = 69ms, so 20ms stays a 300 and 60ms is a 100.
+        let hard_rock = rescore_osu_variant(
+            &replay,
+            &chart,
+            0.0,
+            10.0,
+            OsuRulesetVariant::STABLE.with_mod(OsuDifficultyMod::HardRock),
+        );
+        assert_eq!(hard_rock.target.count(OsuJudgement::Perfect), 1);
+        assert_eq!(hard_rock.target.count(OsuJudgement::Good), 1);
+
+        // Lazer at OD10: MAX = 13.5ms, 300 = 34.5ms and 200 = 67.5ms
+        let lazer = rescore_osu_variant(&replay, &chart, 10.0, 10.0, OsuRulesetVariant::LAZER);
+        assert_eq!(lazer.target.count(OsuJudgement::Marvelous), 1);
+        assert_eq!(lazer.target.count(OsuJudgement::Perfect), 1);
+        assert_eq!(lazer.target.count(OsuJudgement::Great), 1);
+    }
+
+    #[test]
+    fn test_rescore_etterna_judge() {
+        let (chart, replay) = chart_and_replay();
+
+        let report = rescore_etterna(&replay, &chart, 4, 7);
+
+        assert_eq!(report.original.count(EtternaJudgement::Marvelous), 2);
+        assert_eq!(report.original.count(EtternaJudgement::Great), 1);
+        // J7: 20ms is a Perfect and 60ms is a Good, which breaks combo.
+        assert_eq!(report.target.count(EtternaJudgement::Perfect), 1);
+        assert_eq!(report.target.count(EtternaJudgement::Good), 1);
+        assert_eq!(report.target.max_combo, 2);
+        assert!(report.accuracy_delta() < 0.0);
+        assert!(report.original.accuracy <= 1.0);
+    }
+
+    #[test]
+    fn test_rescore_rated_replay() {
+        let chart = Chart::new(4, vec![Note::tap(1_000_000, 0)]);
+        let replays = |delta_us: u64| {
+            let inputs = vec![ReplayInput::new(1_000_000 + delta_us, 0, true, false)];
+            (
+                ReplayData::with_params(1.5, None, inputs.clone()),
+                ReplayData::new(inputs),
+            )
+        };
+
+        // 45ms late in song time is 30ms late in real time at 1.5x
+        let (rated, unrated) = replays(45_000);
+        let report = rescore_osu(&rated, &chart, 10.0, 10.0);
+        assert_eq!(report.original.count(OsuJudgement::Perfect), 1);
+        assert_eq!(report.original.results[0].delta_us, Some(30_000));
+        let report = rescore_osu(&unrated, &chart, 10.0, 10.0);
+        assert_eq!(report.original.count(OsuJudgement::Great), 1);
+
+        // 60ms late in song time is 40ms late in real time at 1.5x
+        let (rated, unrated) = replays(60_000);
+        let rated = rescore_etterna(&rated, &chart, 4, 4);
+        let unrated = rescore_etterna(&unrated, &chart, 4, 4);
+        assert_eq!(rated.original.count(EtternaJudgement::Perfect), 1);
+        assert_eq!(unrated.original.count(EtternaJudgement::Great), 1);
+        assert!(rated.original.accuracy > unrated.original.accuracy);
+    }
+}
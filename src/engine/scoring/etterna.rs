@@ -0,0 +1,296 @@
+//! Etterna Wife3 accuracy, plus legacy Wife2 and DP scoring.
+//!
+//! Wife scoring ranks plays on the raw hit offset of every note instead of
+//! judgement buckets. Each tap is worth up to 2 points; misses, mine hits and
+//! hold drops subtract points. Accuracy is the percentage of the maximum.
+
+use crate::engine::scoring::ScoredJudgement;
+use crate::engine::simulator::NoteJudgement;
+use crate::engine::timing::TailJudgement;
+use crate::engine::timing::hit_windows::EtternaJudgement;
+
+/// Maximum points of a single tap.
+const MAX_POINTS: f64 = 2.0;
+
+const WIFE3_MISS_WEIGHT: f64 = -5.5;
+const WIFE3_MINE_HIT_WEIGHT: f64 = -7.0;
+const WIFE3_HOLD_DROP_WEIGHT: f64 = -4.5;
+
+const WIFE2_MISS_WEIGHT: f64 = -8.0;
+const WIFE2_MINE_HIT_WEIGHT: f64 = -8.0;
+const WIFE2_HOLD_DROP_WEIGHT: f64 = -6.0;
+
+/// Returns the timing scale of an Etterna judge level (J4 = 1.0).
+/// Levels outside 1-9 are clamped.
+#[must_use]
+pub fn timing_scale(judge_level: i64) -> f64 {
+    match judge_level.clamp(1, 9) {
+        1 => 1.50,
+        2 => 1.33,
+        3 => 1.16,
+        4 => 1.00,
+        5 => 0.84,
+        6 => 0.66,
+        7 => 0.50,
+        8 => 0.33,
+        _ => 0.20,
+    }
+}
+
+/// Returns the Wife3 points of a tap hit `offset_us` away from the note.
+///
+/// Full points up to `5ms * scale`, then an erf curve reaching zero at
+/// `65ms * scale^0.75`, then a linear slope down to the miss weight at
+/// `180ms * scale`.
+#[must_use]
+pub fn wife3_points(offset_us: i64, judge_level: i64) -> f64 {
+    let scale = timing_scale(judge_level);
+    let offset_ms = (offset_us as f64 / 1000.0).abs();
+
+    let ridic = 5.0 * scale;
+    let max_boo_weight = 180.0 * scale;
+
+    if offset_ms <= ridic {
+        return MAX_POINTS;
+    }
+
+    let zero = 65.0 * scale.powf(0.75);
+    let dev = 22.7 * scale.powf(0.75);
+
+    if offset_ms <= zero {
+        return MAX_POINTS * libm::erf((zero - offset_ms) / dev);
+    }
+    if offset_ms <= max_boo_weight {
+        return (offset_ms - zero) * WIFE3_MISS_WEIGHT / (max_boo_weight - zero);
+    }
+    WIFE3_MISS_WEIGHT
+}
+
+/// Returns the legacy Wife2 points of a tap hit `offset_us` away from the note.
+#[must_use]
+pub fn wife2_points(offset_us: i64, judge_level: i64) -> f64 {
+    let offset_ms = offset_us as f64 / 1000.0;
+    let deviation = 95.0 * timing_scale(judge_level);
+
+    let y = 1.0 - 2f64.powf(-(offset_ms * offset_ms) / (deviation * deviation));
+    let y = y * y;
+    (MAX_POINTS - WIFE2_MISS_WEIGHT) * (1.0 - y) + WIFE2_MISS_WEIGHT
+}
+
+impl ScoredJudgement for EtternaJudgement {
+    /// DP weight relative to a Marvelous (2 points).
+    fn accuracy_weight(&self) -> f64 {
+        match self {
+            EtternaJudgement::Marvelous | EtternaJudgement::Perfect => 1.0,
+            EtternaJudgement::Great => 0.5,
+            EtternaJudgement::Good => 0.0,
+            EtternaJudgement::Bad | EtternaJudgement::Boo => -2.0,
+            EtternaJudgement::Miss => -4.0,
+        }
+    }
+
+    /// Only Great and better continue the combo in Etterna.
+    fn breaks_combo(&self) -> bool {
+        matches!(
+            self,
+            EtternaJudgement::Good
+                | EtternaJudgement::Bad
+                | EtternaJudgement::Boo
+                | EtternaJudgement::Miss
+        )
+    }
+}
+
+/// Raw data needed to compute a Wife score.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EtternaPlay {
+    /// Hit offset in microseconds of every tap (and long note head).
+    /// `None` for misses.
+    pub offsets: Vec<Option<i64>>,
+
+    /// Number of mines hit.
+    pub mine_hits: u32,
+
+    /// Number of long notes dropped or never grabbed.
+    pub hold_drops: u32,
+}
+
+impl EtternaPlay {
+    /// Collects offsets and hold drops from simulator results.
+    ///
+    /// Mines are not judged by the simulator; use `engine::mine_hits` to
+    /// count them.
+    #[must_use]
+    pub fn from_results(results: &[NoteJudgement<EtternaJudgement>], mine_hits: u32) -> Self {
+        Self {
+            offsets: results.iter().map(|result| result.delta_us).collect(),
+            mine_hits,
+            hold_drops: results
+                .iter()
+                .filter(|result| result.tail == Some(TailJudgement::Dropped))
+                .count() as u32,
+        }
+    }
+
+    /// Computes the Wife3 accuracy in percent (0-100, can go negative).
+    #[must_use]
+    pub fn wife3(&self, judge_level: i64) -> f64 {
+        self.percent(
+            |offset_us| wife3_points(offset_us, judge_level),
+            WIFE3_MISS_WEIGHT,
+            WIFE3_MINE_HIT_WEIGHT,
+            WIFE3_HOLD_DROP_WEIGHT,
+        )
+    }
+
+    /// Computes the legacy Wife2 accuracy in percent.
+    #[must_use]
+    pub fn wife2(&self, judge_level: i64) -> f64 {
+        self.percent(
+            |offset_us| wife2_points(offset_us, judge_level),
+            WIFE2_MISS_WEIGHT,
+            WIFE2_MINE_HIT_WEIGHT,
+            WIFE2_HOLD_DROP_WEIGHT,
+        )
+    }
+
+    fn percent(
+        &self,
+        points: impl Fn(i64) -> f64,
+        miss_weight: f64,
+        mine_hit_weight: f64,
+        hold_drop_weight: f64,
+    ) -> f64 {
+        if self.offsets.is_empty() {
+            return 0.0;
+        }
+
+        let tap_points: f64 = self
+            .offsets
+            .iter()
+            .map(|offset| offset.map_or(miss_weight, &points))
+            .sum();
+        let total = tap_points
+            + self.mine_hits as f64 * mine_hit_weight
+            + self.hold_drops as f64 * hold_drop_weight;
+
+        total / (self.offsets.len() as f64 * MAX_POINTS) * 100.0
+    }
+}
+
+/// Computes the legacy DP (dance points) percentage from judgements.
+///
+/// Taps: Marvelous/Perfect 2, Great 1, Good 0, Bad/Boo -4, Miss -8.
+/// Long notes: +6 when held, 0 when dropped. Mines hit: -8.
+#[must_use]
+pub fn dp_percent(results: &[NoteJudgement<EtternaJudgement>], mine_hits: u32) -> f64 {
+    let mut points = 0i64;
+    let mut max_points = 0i64;
+
+    for result in results {
+        points += match result.judgement {
+            EtternaJudgement::Marvelous | EtternaJudgement::Perfect => 2,
+            EtternaJudgement::Great => 1,
+            EtternaJudgement::Good => 0,
+            EtternaJudgement::Bad | EtternaJudgement::Boo => -4,
+            EtternaJudgement::Miss => -8,
+        };
+        max_points += 2;
+
+        if let Some(tail) = result.tail {
+            if tail == TailJudgement::Held {
+                points += 6;
+            }
+            max_points += 6;
+        }
+    }
+    points -= 8 * mine_hits as i64;
+
+    if max_points == 0 {
+        return 0.0;
+    }
+    points as f64 / max_points as f64 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn test_wife3_curve_j4() {
+        assert_eq!(wife3_points(0, 4), 2.0);
+        assert_eq!(wife3_points(-5_000, 4), 2.0);
+        assert!(close(wife3_points(65_000, 4), 0.0));
+        assert!(close(wife3_points(-180_000, 4), -5.5));
+        assert_eq!(wife3_points(500_000, 4), -5.5);
+
+        // Symmetric and decreasing
+        assert_eq!(wife3_points(20_000, 4), wife3_points(-20_000, 4));
+        assert!(wife3_points(10_000, 4) > wife3_points(20_000, 4));
+        assert!(wife3_points(20_000, 4) > wife3_points(40_000, 4));
+    }
+
+    #[test]
+    fn test_wife3_judge_scaling() {
+        // Stricter judges give fewer points for the same offset.
+        assert!(wife3_points(30_000, 7) < wife3_points(30_000, 4));
+        assert!(wife3_points(30_000, 4) < wife3_points(30_000, 1));
+        assert_eq!(timing_scale(0), timing_scale(1));
+        assert_eq!(timing_scale(12), timing_scale(9));
+    }
+
+    #[test]
+    fn test_wife3_percent() {
+        let play = EtternaPlay {
+            offsets: vec![Some(0), Some(3_000), Some(-1_000), None],
+            mine_hits: 0,
+            hold_drops: 0,
+        };
+        assert!(close(play.wife3(4), (6.0 - 5.5) / 8.0 * 100.0));
+
+        let penalised = EtternaPlay {
+            mine_hits: 1,
+            hold_drops: 1,
+            ..play.clone()
+        };
+        assert!(close(
+            penalised.wife3(4),
+            (6.0 - 5.5 - 7.0 - 4.5) / 8.0 * 100.0
+        ));
+    }
+
+    #[test]
+    fn test_wife2() {
+        assert!(close(wife2_points(0, 4), 2.0));
+        assert!(wife2_points(50_000, 4) < 2.0);
+
+        let play = EtternaPlay {
+            offsets: vec![Some(0), None],
+            ..Default::default()
+        };
+        assert!(close(play.wife2(4), (2.0 - 8.0) / 4.0 * 100.0));
+    }
+
+    #[test]
+    fn test_dp_percent() {
+        let result = |judgement, tail| NoteJudgement {
+            note_index: 0,
+            judgement,
+            delta_us: None,
+            tail,
+        };
+        let results = [
+            result(EtternaJudgement::Marvelous, None),
+            result(EtternaJudgement::Great, None),
+            result(EtternaJudgement::Perfect, Some(TailJudgement::Held)),
+            result(EtternaJudgement::Miss, Some(TailJudgement::Dropped)),
+        ];
+
+        // (2 + 1 + 2 + 6 - 8 - 8) / (2 * 4 + 6 * 2)
+        assert!(close(dp_percent(&results, 1), -5.0 / 20.0 * 100.0));
+    }
+}
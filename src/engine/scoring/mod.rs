@@ -0,0 +1,128 @@
+pub mod etterna;
+pub mod osu;
+
+pub use etterna::EtternaPlay;
+pub use osu::{OsuGrade, OsuJudgementCounts, OsuScore};
+
+use crate::engine::simulator::NoteJudgement;
+use crate::engine::timing::hit_windows::{GhJudgement, ItgJudgement, QuaverJudgement};
+use crate::engine::timing::{DynamicJudgement, TailJudgement};
+use crate::model::Chart;
+
+/// Judgement that can be scored independently of its game.
+pub trait ScoredJudgement: Copy + PartialEq {
+    /// Accuracy value of the judgement, 1.0 being a perfect hit.
+    fn accuracy_weight(&self) -> f64;
+
+    /// Checks if the judgement resets the combo.
+    fn breaks_combo(&self) -> bool;
+}
+
+impl ScoredJudgement for GhJudgement {
+    fn accuracy_weight(&self) -> f64 {
+        match self {
+            GhJudgement::Hit => 1.0,
+            GhJudgement::Miss => 0.0,
+        }
+    }
+
+    fn breaks_combo(&self) -> bool {
+        *self == GhJudgement::Miss
+    }
+}
+
+impl ScoredJudgement for ItgJudgement {
+    /// ITG dance point weights relative to a Fantastic (5 points).
+    fn accuracy_weight(&self) -> f64 {
+        match self {
+            ItgJudgement::BlueFantastic | ItgJudgement::Fantastic => 1.0,
+            ItgJudgement::Excellent => 0.8,
+            ItgJudgement::Great => 0.4,
+            ItgJudgement::Decent => 0.0,
+            ItgJudgement::WayOff => -1.2,
+            ItgJudgement::Miss => -2.4,
+        }
+    }
+
+    /// Only Great and better continue the combo in ITG.
+    fn breaks_combo(&self) -> bool {
+        matches!(
+            self,
+            ItgJudgement::Decent | ItgJudgement::WayOff | ItgJudgement::Miss
+        )
+    }
+}
+
+impl ScoredJudgement for QuaverJudgement {
+    /// Quaver accuracy weights: Okay and Miss subtract accuracy.
+    fn accuracy_weight(&self) -> f64 {
+        match self {
+            QuaverJudgement::Marvelous => 1.0,
+            QuaverJudgement::Perfect => 0.9825,
+            QuaverJudgement::Great => 0.65,
+            QuaverJudgement::Good => 0.25,
+            QuaverJudgement::Okay => -1.0,
+            QuaverJudgement::Miss => -0.5,
+        }
+    }
+
+    fn breaks_combo(&self) -> bool {
+        *self == QuaverJudgement::Miss
+    }
+}
+
+impl ScoredJudgement for DynamicJudgement {
+    fn accuracy_weight(&self) -> f64 {
+        self.accuracy_weight
+    }
+
+    fn breaks_combo(&self) -> bool {
+        self.breaks_combo
+    }
+}
+
+/// Returns the mean accuracy weight of a judgement sequence, up to 1.0.
+/// Negative weights can bring it below zero.
+#[must_use]
+pub fn accuracy<J: ScoredJudgement>(judgements: &[J]) -> f64 {
+    if judgements.is_empty() {
+        return 1.0;
+    }
+    let total: f64 = judgements.iter().map(J::accuracy_weight).sum();
+    total / judgements.len() as f64
+}
+
+/// Returns the highest number of consecutive judgements not breaking combo.
+#[must_use]
+pub fn max_combo<J: ScoredJudgement>(judgements: &[J]) -> u32 {
+    let mut combo = 0u32;
+    let mut max = 0u32;
+    for judgement in judgements {
+        if judgement.breaks_combo() {
+            combo = 0;
+        } else {
+            combo += 1;
+            max = max.max(combo);
+        }
+    }
+    max
+}
+
+/// Flattens simulator results into a chronological judgement sequence.
+///
+/// Long note tails judged by release windows are placed at the tail time.
+/// Hold-drop tails (`Held` / `Dropped`) carry no judgement and are skipped.
+#[must_use]
+pub fn judgement_sequence<J: Copy>(chart: &Chart, results: &[NoteJudgement<J>]) -> Vec<J> {
+    let mut timed: Vec<(i64, J)> = Vec::with_capacity(results.len());
+    for result in results {
+        let note = &chart.notes[result.note_index];
+        timed.push((note.time_us, result.judgement));
+        if let Some(TailJudgement::Release { judgement, .. }) = result.tail {
+            timed.push((note.end_us(), judgement));
+        }
+    }
+
+    timed.sort_by_key(|&(time_us, _)| time_us);
+    timed.into_iter().map(|(_, judgement)| judgement).collect()
+}
@@ -0,0 +1,388 @@
+//! osu!mania score, accuracy, combo and grade calculation.
+//!
+//! Judgements map to the osu!mania names as follows:
+//! `Marvelous` = MAX (300g), `Perfect` = 300, `Great` = 200, `Good` = 100,
+//! `Bad` = 50 and `Miss`.
+
+pub use crate::codec::formats::osu::grade::OsuGrade;
+use crate::codec::formats::osu::types::OsuReplay;
+use crate::engine::scoring::ScoredJudgement;
+use crate::engine::timing::hit_windows::OsuJudgement;
+
+/// Maximum score of a play.
+const MAX_SCORE: f64 = 1_000_000.0;
+
+/// Judgement counts of an osu!mania play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OsuJudgementCounts {
+    pub marvelous: u32,
+    pub perfect: u32,
+    pub great: u32,
+    pub good: u32,
+    pub bad: u32,
+    pub miss: u32,
+}
+
+impl OsuJudgementCounts {
+    /// Counts a judgement sequence.
+    #[must_use]
+    pub fn from_judgements(judgements: &[OsuJudgement]) -> Self {
+        let mut counts = Self::default();
+        for judgement in judgements {
+            counts.add(*judgement);
+        }
+        counts
+    }
+
+    /// Reads the `count_*` fields of an osu!mania replay.
+    #[must_use]
+    pub fn from_replay(replay: &OsuReplay) -> Self {
+        Self {
+            marvelous: replay.count_geki as u32,
+            perfect: replay.count_300 as u32,
+            great: replay.count_katu as u32,
+            good: replay.count_100 as u32,
+            bad: replay.count_50 as u32,
+            miss: replay.count_miss as u32,
+        }
+    }
+
+    /// Adds a single judgement.
+    pub fn add(&mut self, judgement: OsuJudgement) {
+        match judgement {
+            OsuJudgement::Marvelous => self.marvelous += 1,
+            OsuJudgement::Perfect => self.perfect += 1,
+            OsuJudgement::Great => self.great += 1,
+            OsuJudgement::Good => self.good += 1,
+            OsuJudgement::Bad => self.bad += 1,
+            OsuJudgement::Miss => self.miss += 1,
+        }
+    }
+
+    /// Returns the total number of judgements.
+    #[must_use]
+    pub fn total(&self) -> u32 {
+        self.marvelous + self.perfect + self.great + self.good + self.bad + self.miss
+    }
+
+    /// Returns the osu! stable (ScoreV1) accuracy, from 0.0 to 1.0.
+    /// MAX and 300 are both worth 300.
+    #[must_use]
+    pub fn accuracy_v1(&self) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            return 1.0;
+        }
+        let points = 300 * (self.marvelous + self.perfect) as u64
+            + 200 * self.great as u64
+            + 100 * self.good as u64
+            + 50 * self.bad as u64;
+        points as f64 / (300 * total as u64) as f64
+    }
+
+    /// Returns the ScoreV2 / osu!lazer accuracy, from 0.0 to 1.0.
+    /// MAX is worth 305 and 300 is worth 300.
+    #[must_use]
+    pub fn accuracy_v2(&self) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            return 1.0;
+        }
+        let points = 305 * self.marvelous as u64
+            + 300 * self.perfect as u64
+            + 200 * self.great as u64
+            + 100 * self.good as u64
+            + 50 * self.bad as u64;
+        points as f64 / (305 * total as u64) as f64
+    }
+
+    /// Writes the counts into the `count_*` fields of an osu!mania replay.
+    /// Counts above `u16::MAX` are saturated.
+    pub fn apply_to(&self, replay: &mut OsuReplay) {
+        let to_u16 = |count: u32| count.min(u16::MAX as u32) as u16;
+        replay.count_geki = to_u16(self.marvelous);
+        replay.count_300 = to_u16(self.perfect);
+        replay.count_katu = to_u16(self.great);
+        replay.count_100 = to_u16(self.good);
+        replay.count_50 = to_u16(self.bad);
+        replay.count_miss = to_u16(self.miss);
+    }
+}
+
+impl ScoredJudgement for OsuJudgement {
+    /// Stable (ScoreV1) accuracy weight: MAX and 300 are worth 300/300.
+    fn accuracy_weight(&self) -> f64 {
+        match self {
+            OsuJudgement::Marvelous | OsuJudgement::Perfect => 1.0,
+            OsuJudgement::Great => 200.0 / 300.0,
+            OsuJudgement::Good => 100.0 / 300.0,
+            OsuJudgement::Bad => 50.0 / 300.0,
+            OsuJudgement::Miss => 0.0,
+        }
+    }
+
+    fn breaks_combo(&self) -> bool {
+        *self == OsuJudgement::Miss
+    }
+}
+
+/// Returns the highest number of consecutive non-miss judgements.
+#[must_use]
+pub fn max_combo(judgements: &[OsuJudgement]) -> u32 {
+    super::max_combo(judgements)
+}
+
+/// Computes the classic osu!mania ScoreV1.
+///
+/// Half of the score comes from hit values, the other half from a bonus
+/// scaled by the square root of a 0-100 "bonus" gauge, raised by MAX/300 and
+/// lowered by worse judgements.
+///
+/// # Arguments
+///
+/// * `judgements` - Chronological judgement sequence (heads and tails)
+/// * `mod_multiplier` - Score multiplier of the enabled mods (1.0 = nomod)
+#[must_use]
+pub fn score_v1(judgements: &[OsuJudgement], mod_multiplier: f64) -> u32 {
+    if judgements.is_empty() {
+        return 0;
+    }
+
+    let note_value = MAX_SCORE * mod_multiplier * 0.5 / judgements.len() as f64;
+    let mut bonus = 100.0f64;
+    let mut score = 0.0f64;
+
+    for judgement in judgements {
+        // (hit value, hit bonus value, hit bonus, hit punishment)
+        let (hit_value, hit_bonus_value, hit_bonus, hit_punishment) = match judgement {
+            OsuJudgement::Marvelous => (320.0, 32.0, 2.0, 0.0),
+            OsuJudgement::Perfect => (300.0, 32.0, 1.0, 0.0),
+            OsuJudgement::Great => (200.0, 16.0, 0.0, 8.0),
+            OsuJudgement::Good => (100.0, 8.0, 0.0, 24.0),
+            OsuJudgement::Bad => (50.0, 4.0, 0.0, 44.0),
+            OsuJudgement::Miss => (0.0, 0.0, 0.0, 100.0),
+        };
+
+        bonus = (bonus + hit_bonus - hit_punishment).clamp(0.0, 100.0);
+
+        let base_score = note_value * (hit_value / 320.0);
+        let bonus_score = note_value * (hit_bonus_value * bonus.sqrt() / 320.0);
+        score += base_score + bonus_score;
+    }
+
+    score.round() as u32
+}
+
+/// Computes the osu!mania ScoreV2 (osu!lazer standardised scoring).
+///
+/// 99% of the score comes from the accuracy (`accuracy_v2`), raised to the
+/// power `2 + 2 * accuracy`. The remaining 1% rewards combo, each hit being
+/// weighted by `clamp(log4(combo), 0.5, log4(400))`.
+///
+/// # Arguments
+///
+/// * `judgements` - Chronological judgement sequence (heads and tails)
+/// * `mod_multiplier` - Score multiplier of the enabled mods (1.0 = nomod)
+#[must_use]
+pub fn score_v2(judgements: &[OsuJudgement], mod_multiplier: f64) -> u32 {
+    if judgements.is_empty() {
+        return 0;
+    }
+
+    let combo_weight = |combo: u32| (combo as f64).log(4.0).clamp(0.5, 400f64.log(4.0));
+
+    let mut combo = 0u32;
+    let mut combo_progress = 0.0f64;
+    let mut max_combo_progress = 0.0f64;
+
+    for (index, judgement) in judgements.iter().enumerate() {
+        let base_score = match judgement {
+            OsuJudgement::Marvelous => 305.0,
+            OsuJudgement::Perfect => 300.0,
+            OsuJudgement::Great => 200.0,
+            OsuJudgement::Good => 100.0,
+            OsuJudgement::Bad => 50.0,
+            OsuJudgement::Miss => 0.0,
+        };
+
+        if *judgement == OsuJudgement::Miss {
+            combo = 0;
+        } else {
+            combo += 1;
+        }
+
+        combo_progress += base_score * combo_weight(combo);
+        max_combo_progress += 305.0 * combo_weight(index as u32 + 1);
+    }
+
+    let accuracy = OsuJudgementCounts::from_judgements(judgements).accuracy_v2();
+    let combo_portion = 10_000.0 * combo_progress / max_combo_progress;
+    let accuracy_portion = 990_000.0 * accuracy.powf(2.0 + 2.0 * accuracy);
+
+    ((combo_portion + accuracy_portion) * mod_multiplier).round() as u32
+}
+
+/// Score summary of an osu!mania play, as shown on a leaderboard.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OsuScore {
+    pub counts: OsuJudgementCounts,
+    pub score: u32,
+    pub max_combo: u32,
+    /// Accuracy from 0.0 to 1.0, matching the scoring version.
+    pub accuracy: f64,
+    pub grade: OsuGrade,
+    /// Full combo (no miss).
+    pub perfect: bool,
+}
+
+impl OsuScore {
+    /// Scores a judgement sequence with ScoreV1 and stable accuracy.
+    #[must_use]
+    pub fn v1(judgements: &[OsuJudgement], mod_multiplier: f64, silver: bool) -> Self {
+        let counts = OsuJudgementCounts::from_judgements(judgements);
+        let accuracy = counts.accuracy_v1();
+        Self {
+            counts,
+            score: score_v1(judgements, mod_multiplier),
+            max_combo: max_combo(judgements),
+            accuracy,
+            grade: OsuGrade::from_accuracy(accuracy, silver),
+            perfect: counts.miss == 0,
+        }
+    }
+
+    /// Scores a judgement sequence with ScoreV2 and lazer accuracy.
+    #[must_use]
+    pub fn v2(judgements: &[OsuJudgement], mod_multiplier: f64, silver: bool) -> Self {
+        let counts = OsuJudgementCounts::from_judgements(judgements);
+        let accuracy = counts.accuracy_v2();
+        Self {
+            counts,
+            score: score_v2(judgements, mod_multiplier),
+            max_combo: max_combo(judgements),
+            accuracy,
+            grade: OsuGrade::from_accuracy(accuracy, silver),
+            perfect: counts.miss == 0,
+        }
+    }
+
+    /// Writes counts, score, max combo and full combo flag into an osu! replay.
+    pub fn apply_to(&self, replay: &mut OsuReplay) {
+        self.counts.apply_to(replay);
+        replay.score = self.score;
+        replay.max_combo = self.max_combo.min(u16::MAX as u32) as u16;
+        replay.perfect = self.perfect;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::formats::osu::OsuMods;
+    use OsuJudgement::*;
+
+    #[test]
+    fn test_all_marvelous() {
+        let judgements = vec![Marvelous; 500];
+
+        assert_eq!(score_v1(&judgements, 1.0), 1_000_000);
+        assert_eq!(score_v2(&judgements, 1.0), 1_000_000);
+        assert_eq!(max_combo(&judgements), 500);
+
+        let score = OsuScore::v1(&judgements, 1.0, false);
+        assert_eq!(score.accuracy, 1.0);
+        assert_eq!(score.grade, OsuGrade::SS);
+        assert!(score.perfect);
+    }
+
+    #[test]
+    fn test_mod_multiplier() {
+        let judgements = vec![Marvelous; 100];
+        assert_eq!(score_v1(&judgements, 0.5), 500_000);
+        assert_eq!(score_v2(&judgements, 0.5), 500_000);
+    }
+
+    #[test]
+    fn test_accuracy() {
+        let counts = OsuJudgementCounts {
+            marvelous: 1,
+            perfect: 1,
+            great: 1,
+            good: 1,
+            bad: 1,
+            miss: 1,
+        };
+        assert_eq!(counts.total(), 6);
+        assert!((counts.accuracy_v1() - 950.0 / 1800.0).abs() < 1e-12);
+        assert!((counts.accuracy_v2() - 955.0 / 1830.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_score_v1_bonus_recovers() {
+        // A 300 keeps the bonus gauge full but is worth less than a MAX.
+        let with_300 = score_v1(&[Marvelous, Perfect, Marvelous, Marvelous], 1.0);
+        // A miss empties the gauge, which then has to refill.
+        let with_miss = score_v1(&[Marvelous, Miss, Marvelous, Marvelous], 1.0);
+
+        assert!(with_300 < 1_000_000);
+        assert!(with_miss < with_300);
+    }
+
+    #[test]
+    fn test_max_combo() {
+        assert_eq!(
+            max_combo(&[Perfect, Miss, Good, Bad, Great, Miss, Marvelous]),
+            3
+        );
+        assert_eq!(max_combo(&[Miss, Miss]), 0);
+        assert_eq!(max_combo(&[]), 0);
+    }
+
+    #[test]
+    fn test_grades() {
+        assert_eq!(OsuGrade::from_accuracy(1.0, true), OsuGrade::SSH);
+        assert_eq!(OsuGrade::from_accuracy(0.96, false), OsuGrade::S);
+        assert_eq!(OsuGrade::from_accuracy(0.95, false), OsuGrade::A);
+        assert_eq!(OsuGrade::from_accuracy(0.85, false), OsuGrade::B);
+        assert_eq!(OsuGrade::from_accuracy(0.75, false), OsuGrade::C);
+        assert_eq!(OsuGrade::from_accuracy(0.5, false), OsuGrade::D);
+    }
+
+    #[test]
+    fn test_apply_to_replay_roundtrip() {
+        use crate::codec::formats::osu::types::GameMode;
+
+        let mut replay = OsuReplay {
+            mode: GameMode::Mania,
+            game_version: 0,
+            beatmap_hash: String::new(),
+            username: String::new(),
+            replay_hash: String::new(),
+            count_300: 0,
+            count_100: 0,
+            count_50: 0,
+            count_geki: 0,
+            count_katu: 0,
+            count_miss: 0,
+            score: 0,
+            max_combo: 0,
+            perfect: false,
+            mods: OsuMods::NONE,
+            life_bar_graph: Vec::new(),
+            timestamp: 0,
+            replay_data: Vec::new(),
+            rng_seed: None,
+            replay_id: 0,
+            lazer_score_info: None,
+        };
+
+        let judgements = [Marvelous, Perfect, Great, Good, Bad, Marvelous];
+        let score = OsuScore::v1(&judgements, 1.0, false);
+        score.apply_to(&mut replay);
+
+        assert_eq!(OsuJudgementCounts::from_replay(&replay), score.counts);
+        assert_eq!(replay.score, score.score);
+        assert_eq!(replay.max_combo, 6);
+        assert!(replay.perfect);
+    }
+}
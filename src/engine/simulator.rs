@@ -0,0 +1,624 @@
+//! Replay judging simulator.
+//!
+//! Plays the `ReplayInput` stream of a `ReplayData` against a chart's notes
+//! through any `HitWindows` implementation and produces one judgement per note.
+//!
+//! Input deltas are accumulated into absolute times and compared against
+//! note times, both in integer microseconds of chart time. Hit windows are in
+//! real time, so offsets are divided by `ReplayData::rate` before judging.
+
+use crate::engine::timing::{HitWindows, TailJudgement, TailRule};
+use crate::model::{Chart, Note, ReplayData};
+
+/// Judgement result of a single note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteJudgement<J> {
+    /// Index of the note in `Chart::notes`.
+    pub note_index: usize,
+
+    /// Judgement assigned by the hit windows.
+    /// For long notes, this is the judgement of the head.
+    pub judgement: J,
+
+    /// Hit offset in microseconds of real time (input time - note time,
+    /// divided by the replay rate). `None` if the note was never hit.
+    pub delta_us: Option<i64>,
+
+    /// Judgement of the tail for long notes.
+    /// `None` for tap notes, or when tails are not judged.
+    pub tail: Option<TailJudgement<J>>,
+}
+
+/// Judges every note of a chart against the inputs of a replay.
+///
+/// Notes are matched per column, in chronological order:
+/// - A press judges the oldest pending note of its column.
+/// - A press too early for that note (`judge` returns `None`) is ignored.
+/// - A note whose late limit has passed is missed and never consumes a press.
+///
+/// Releases and inputs on columns without notes are ignored.
+/// Mines are not judged, and long notes are judged on their head only.
+/// Use [`simulate_holds`] to judge long note tails as well.
+///
+/// # Returns
+///
+/// One `NoteJudgement` per hittable note, in chart order.
+#[must_use]
+pub fn simulate<W>(
+    replay: &ReplayData,
+    chart: &Chart,
+    windows: &W,
+) -> Vec<NoteJudgement<W::Judgement>>
+where
+    W: HitWindows,
+    W::Judgement: Copy,
+{
+    Simulation::<W, W>::new(chart, windows, None, replay.rate).run(replay)
+}
+
+/// Judges every note of a chart, including long note tails.
+///
+/// Heads are judged as in [`simulate`]. Once a long note head is hit, the
+/// press is paired with the next release of its column and the tail is judged
+/// according to `tail_rule`. Tails of missed heads are missed as well.
+///
+/// # Returns
+///
+/// One `NoteJudgement` per hittable note, in chart order, with `tail` set for
+/// every long note.
+#[must_use]
+pub fn simulate_holds<W, R>(
+    replay: &ReplayData,
+    chart: &Chart,
+    windows: &W,
+    tail_rule: &TailRule<R>,
+) -> Vec<NoteJudgement<W::Judgement>>
+where
+    W: HitWindows,
+    W::Judgement: Copy,
+    R: HitWindows<Judgement = W::Judgement>,
+{
+    Simulation::new(chart, windows, Some(tail_rule), replay.rate).run(replay)
+}
+
+/// Finds the mines of a chart hit by a replay.
+///
+/// A mine is hit when its column is pressed within `window_us` (real time) of
+/// the mine, or when the column is held down as the mine passes.
+///
+/// # Returns
+///
+/// Indices in `Chart::notes` of the mines that were hit, in chart order.
+#[must_use]
+pub fn mine_hits(replay: &ReplayData, chart: &Chart, window_us: i64) -> Vec<usize> {
+    // Per-column (press time, release time) intervals.
+    let mut held: [Vec<(i64, i64)>; 16] = Default::default();
+    let mut pressed_at: [Option<i64>; 16] = [None; 16];
+
+    let mut time_us = replay.start_offset_us;
+    for input in &replay.inputs {
+        time_us += input.delta_us as i64;
+        let column = input.column() as usize;
+        if input.is_press() {
+            pressed_at[column].get_or_insert(time_us);
+        } else if let Some(start_us) = pressed_at[column].take() {
+            held[column].push((start_us, time_us));
+        }
+    }
+    for (column, start_us) in pressed_at.iter().enumerate() {
+        if let Some(start_us) = start_us {
+            held[column].push((*start_us, i64::MAX));
+        }
+    }
+
+    chart
+        .notes
+        .iter()
+        .enumerate()
+        .filter(|(_, note)| note.is_mine() && (note.column as usize) < held.len())
+        .filter(|(_, note)| {
+            held[note.column as usize]
+                .iter()
+                .any(|&(start_us, end_us)| {
+                    let pressed_near =
+                        real_time_us(start_us - note.time_us, replay.rate).abs() <= window_us;
+                    let held_through = start_us <= note.time_us && note.time_us < end_us;
+                    pressed_near || held_through
+                })
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Converts a chart time interval to real time at `rate`.
+fn real_time_us(chart_us: i64, rate: f64) -> i64 {
+    if rate == 1.0 {
+        return chart_us;
+    }
+    (chart_us as f64 / rate).round() as i64
+}
+
+/// Long note currently held (or briefly released) in a column.
+struct ActiveHold {
+    note_index: usize,
+    released_at: Option<i64>,
+}
+
+/// Judging state of a single column.
+#[derive(Default)]
+struct ColumnState {
+    /// Note indices of the column, sorted by time.
+    queue: Vec<usize>,
+    /// Index in `queue` of the oldest pending note.
+    cursor: usize,
+    hold: Option<ActiveHold>,
+}
+
+struct Simulation<'a, W: HitWindows, R> {
+    notes: &'a [Note],
+    windows: &'a W,
+    tail_rule: Option<&'a TailRule<R>>,
+    /// Playback rate of the replay, to judge offsets in real time.
+    rate: f64,
+    columns: Vec<ColumnState>,
+    results: Vec<Option<NoteJudgement<W::Judgement>>>,
+}
+
+impl<'a, W, R> Simulation<'a, W, R>
+where
+    W: HitWindows,
+    W::Judgement: Copy,
+    R: HitWindows<Judgement = W::Judgement>,
+{
+    fn new(
+        chart: &'a Chart,
+        windows: &'a W,
+        tail_rule: Option<&'a TailRule<R>>,
+        rate: f64,
+    ) -> Self {
+        let notes = chart.notes.as_slice();
+        let column_count = notes
+            .iter()
+            .map(|n| n.column as usize + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut columns: Vec<ColumnState> =
+            (0..column_count).map(|_| ColumnState::default()).collect();
+        for (index, note) in notes.iter().enumerate() {
+            if note.is_mine() {
+                continue;
+            }
+            columns[note.column as usize].queue.push(index);
+        }
+        for column in &mut columns {
+            column.queue.sort_by_key(|&index| notes[index].time_us);
+        }
+
+        Self {
+            notes,
+            windows,
+            tail_rule,
+            rate,
+            columns,
+            results: vec![None; notes.len()],
+        }
+    }
+
+    fn run(mut self, replay: &ReplayData) -> Vec<NoteJudgement<W::Judgement>> {
+        let mut time_us = replay.start_offset_us;
+        for input in &replay.inputs {
+            time_us += input.delta_us as i64;
+
+            let column = input.column() as usize;
+            if column >= self.columns.len() {
+                continue;
+            }
+
+            self.settle(column, time_us);
+            if input.is_press() {
+                self.press(column, time_us);
+            } else {
+                self.release(column, time_us);
+            }
+        }
+
+        for column in 0..self.columns.len() {
+            self.settle(column, i64::MAX);
+        }
+
+        let windows = self.windows;
+        let notes = self.notes;
+        let missed_tail = self.tail_rule.map(TailRule::missed);
+        self.results
+            .into_iter()
+            .enumerate()
+            .filter(|&(index, _)| !notes[index].is_mine())
+            .map(|(index, result)| {
+                result.unwrap_or(NoteJudgement {
+                    note_index: index,
+                    judgement: windows.miss(),
+                    delta_us: None,
+                    tail: missed_tail.filter(|_| notes[index].is_hold()),
+                })
+            })
+            .collect()
+    }
+
+    /// Resolves everything in `column` that can no longer change at `time_us`:
+    /// long notes held or dropped long enough, and notes past their late limit.
+    fn settle(&mut self, column: usize, time_us: i64) {
+        if let (Some(rule), Some(hold)) = (self.tail_rule, &self.columns[column].hold) {
+            let end_us = self.notes[hold.note_index].end_us();
+            let tail = match (rule, hold.released_at) {
+                (TailRule::Release(windows), _) => {
+                    let held_for_us = real_time_us(time_us.saturating_sub(end_us), self.rate);
+                    (held_for_us > windows.late_limit()).then(|| rule.held_past())
+                }
+                (TailRule::HoldDrop { grace_us }, Some(released_at)) => {
+                    (real_time_us(time_us.saturating_sub(released_at), self.rate) > *grace_us)
+                        .then_some(TailJudgement::Dropped)
+                }
+                (TailRule::HoldDrop { .. }, None) => {
+                    (time_us >= end_us).then_some(TailJudgement::Held)
+                }
+            };
+
+            if let Some(tail) = tail {
+                self.set_tail(hold.note_index, tail);
+                self.columns[column].hold = None;
+            }
+        }
+
+        let late_limit = self.windows.late_limit();
+        let state = &mut self.columns[column];
+        while let Some(&index) = state.queue.get(state.cursor) {
+            let delta_us =
+                real_time_us(time_us.saturating_sub(self.notes[index].time_us), self.rate);
+            if delta_us <= late_limit {
+                break;
+            }
+            self.results[index] = Some(NoteJudgement {
+                note_index: index,
+                judgement: self.windows.miss(),
+                delta_us: None,
+                tail: self
+                    .tail_rule
+                    .filter(|_| self.notes[index].is_hold())
+                    .map(TailRule::missed),
+            });
+            state.cursor += 1;
+        }
+    }
+
+    fn press(&mut self, column: usize, time_us: i64) {
+        let state = &mut self.columns[column];
+
+        // Grab a briefly released long note again.
+        if let Some(hold) = &mut state.hold {
+            hold.released_at = None;
+            return;
+        }
+
+        let Some(&index) = state.queue.get(state.cursor) else {
+            return;
+        };
+        let note = &self.notes[index];
+        let delta_us = real_time_us(time_us - note.time_us, self.rate);
+        let Some(judgement) = self.windows.judge(delta_us) else {
+            return;
+        };
+
+        state.cursor += 1;
+        self.results[index] = Some(NoteJudgement {
+            note_index: index,
+            judgement,
+            delta_us: Some(delta_us),
+            tail: None,
+        });
+
+        if note.is_hold() && self.tail_rule.is_some() {
+            state.hold = Some(ActiveHold {
+                note_index: index,
+                released_at: None,
+            });
+        }
+    }
+
+    fn release(&mut self, column: usize, time_us: i64) {
+        let (Some(rule), Some(hold)) = (self.tail_rule, &mut self.columns[column].hold) else {
+            return;
+        };
+        if hold.released_at.is_some() {
+            return;
+        }
+
+        let delta_us = real_time_us(time_us - self.notes[hold.note_index].end_us(), self.rate);
+        match rule.judge_release(delta_us) {
+            Some(tail) => {
+                let index = hold.note_index;
+                self.columns[column].hold = None;
+                self.set_tail(index, tail);
+            }
+            None => hold.released_at = Some(time_us),
+        }
+    }
+
+    fn set_tail(&mut self, note_index: usize, tail: TailJudgement<W::Judgement>) {
+        if let Some(result) = &mut self.results[note_index] {
+            result.tail = Some(tail);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::timing::TailJudgement;
+    use crate::engine::timing::hit_windows::etterna::{
+        create_etterna_tail_rule, create_etterna_windows,
+    };
+    use crate::engine::timing::hit_windows::osu::{
+        OsuJudgement, create_osu_tail_rule, create_osu_windows,
+    };
+    use crate::model::{Note, ReplayInput};
+
+    fn judgements(results: &[NoteJudgement<OsuJudgement>]) -> Vec<OsuJudgement> {
+        results.iter().map(|r| r.judgement).collect()
+    }
+
+    #[test]
+    fn test_simulate_exact_hits() {
+        let chart = Chart::new(4, vec![Note::tap(1_000_000, 0), Note::tap(1_500_000, 1)]);
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(1_000_000, 0, true, false),
+            ReplayInput::new(50_000, 0, false, false),
+            ReplayInput::new(450_000, 1, true, false),
+            ReplayInput::new(50_000, 1, false, false),
+        ]);
+
+        let results = simulate(&replay, &chart, &create_osu_windows(0.0));
+
+        assert_eq!(
+            judgements(&results),
+            vec![OsuJudgement::Marvelous, OsuJudgement::Marvelous]
+        );
+        assert_eq!(results[0].delta_us, Some(0));
+        assert_eq!(results[1].delta_us, Some(0));
+    }
+
+    #[test]
+    fn test_simulate_offsets() {
+        let chart = Chart::new(4, vec![Note::tap(1_000_000, 0), Note::tap(2_000_000, 0)]);
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(1_050_000, 0, true, false), // +50ms
+            ReplayInput::new(900_000, 0, true, false),   // -50ms
+        ]);
+
+        let results = simulate(&replay, &chart, &create_osu_windows(0.0));
+
+        assert_eq!(
+            judgements(&results),
+            vec![OsuJudgement::Perfect, OsuJudgement::Perfect]
+        );
+        assert_eq!(results[0].delta_us, Some(50_000));
+        assert_eq!(results[1].delta_us, Some(-50_000));
+    }
+
+    #[test]
+    fn test_simulate_unhit_notes_are_missed() {
+        let chart = Chart::new(4, vec![Note::tap(1_000_000, 0), Note::tap(1_000_000, 1)]);
+        let replay = ReplayData::new(vec![ReplayInput::new(1_000_000, 1, true, false)]);
+
+        let results = simulate(&replay, &chart, &create_osu_windows(0.0));
+
+        assert_eq!(
+            judgements(&results),
+            vec![OsuJudgement::Miss, OsuJudgement::Marvelous]
+        );
+        assert_eq!(results[0].delta_us, None);
+    }
+
+    #[test]
+    fn test_simulate_early_press_is_ignored() {
+        let chart = Chart::new(4, vec![Note::tap(1_000_000, 0)]);
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(500_000, 0, true, false), // Far too early
+            ReplayInput::new(500_000, 0, true, false), // Exact
+        ]);
+
+        let results = simulate(&replay, &chart, &create_osu_windows(0.0));
+
+        assert_eq!(judgements(&results), vec![OsuJudgement::Marvelous]);
+    }
+
+    #[test]
+    fn test_simulate_skips_mines() {
+        let chart = Chart::new(4, vec![Note::mine(500_000, 0), Note::tap(1_000_000, 0)]);
+        let replay = ReplayData::new(vec![ReplayInput::new(1_000_000, 0, true, false)]);
+
+        let results = simulate(&replay, &chart, &create_osu_windows(0.0));
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].note_index, 1);
+        assert_eq!(results[0].judgement, OsuJudgement::Marvelous);
+    }
+
+    #[test]
+    fn test_simulate_expired_note_does_not_consume_press() {
+        let chart = Chart::new(4, vec![Note::tap(1_000_000, 0), Note::tap(1_300_000, 0)]);
+        let replay = ReplayData::new(vec![ReplayInput::new(1_300_000, 0, true, false)]);
+
+        let results = simulate(&replay, &chart, &create_osu_windows(0.0));
+
+        assert_eq!(
+            judgements(&results),
+            vec![OsuJudgement::Miss, OsuJudgement::Marvelous]
+        );
+    }
+
+    #[test]
+    fn test_simulate_ignores_tails() {
+        let chart = Chart::new(4, vec![Note::hold(1_000_000, 2_000_000, 0)]);
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(1_000_000, 0, true, false),
+            ReplayInput::new(100_000, 0, false, false),
+        ]);
+
+        let results = simulate(&replay, &chart, &create_osu_windows(0.0));
+
+        assert_eq!(results[0].judgement, OsuJudgement::Marvelous);
+        assert_eq!(results[0].tail, None);
+    }
+
+    #[test]
+    fn test_simulate_holds_release_windows() {
+        let chart = Chart::new(
+            4,
+            vec![
+                Note::hold(1_000_000, 2_000_000, 0),
+                Note::hold(1_000_000, 2_000_000, 1),
+                Note::hold(3_000_000, 4_000_000, 0),
+            ],
+        );
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(1_000_000, 0, true, false),
+            ReplayInput::new(0, 1, true, false),
+            ReplayInput::new(1_020_000, 0, false, false), // Tail +20ms, inside 1.5x MAX
+            ReplayInput::new(80_000, 1, false, false),    // Tail +100ms, past 1.5x 300
+            ReplayInput::new(900_000, 0, true, false),
+            ReplayInput::new(500_000, 0, false, false), // Released halfway
+        ]);
+
+        let results = simulate_holds(
+            &replay,
+            &chart,
+            &create_osu_windows(0.0),
+            &create_osu_tail_rule(0.0),
+        );
+
+        assert_eq!(
+            results[0].tail,
+            Some(TailJudgement::Release {
+                judgement: OsuJudgement::Marvelous,
+                delta_us: Some(20_000),
+            })
+        );
+        assert_eq!(
+            results[1].tail,
+            Some(TailJudgement::Release {
+                judgement: OsuJudgement::Great,
+                delta_us: Some(100_000),
+            })
+        );
+        assert_eq!(results[2].judgement, OsuJudgement::Marvelous);
+        assert_eq!(
+            results[2].tail,
+            Some(TailJudgement::Release {
+                judgement: OsuJudgement::Miss,
+                delta_us: Some(-500_000),
+            })
+        );
+    }
+
+    #[test]
+    fn test_simulate_holds_held_past_and_missed_heads() {
+        let chart = Chart::new(
+            4,
+            vec![
+                Note::hold(1_000_000, 2_000_000, 0),
+                Note::hold(1_000_000, 2_000_000, 1),
+            ],
+        );
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(1_000_000, 0, true, false),
+            ReplayInput::new(2_000_000, 0, false, false), // Held 1s past the tail
+        ]);
+
+        let results = simulate_holds(
+            &replay,
+            &chart,
+            &create_osu_windows(0.0),
+            &create_osu_tail_rule(0.0),
+        );
+
+        assert_eq!(
+            results[0].tail,
+            Some(TailJudgement::Release {
+                judgement: OsuJudgement::Bad,
+                delta_us: None,
+            })
+        );
+        assert_eq!(results[1].judgement, OsuJudgement::Miss);
+        assert_eq!(
+            results[1].tail,
+            Some(TailJudgement::Release {
+                judgement: OsuJudgement::Miss,
+                delta_us: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_simulate_holds_hold_drop() {
+        let chart = Chart::new(
+            4,
+            vec![
+                Note::hold(1_000_000, 2_000_000, 0),
+                Note::hold(1_000_000, 2_000_000, 1),
+                Note::hold(1_000_000, 2_000_000, 2),
+                Note::hold(1_000_000, 2_000_000, 3),
+            ],
+        );
+        let replay = ReplayData::new(vec![
+            // Column 0: held to the end
+            ReplayInput::new(1_000_000, 0, true, false),
+            // Column 1: released for 100ms then grabbed again
+            ReplayInput::new(0, 1, true, false),
+            // Column 2: let go for good at half
+            ReplayInput::new(0, 2, true, false),
+            // Column 3: released 200ms before the end (within grace)
+            ReplayInput::new(0, 3, true, false),
+            ReplayInput::new(400_000, 1, false, false),
+            ReplayInput::new(100_000, 2, false, false),
+            ReplayInput::new(0, 1, true, false),
+            ReplayInput::new(300_000, 3, false, false),
+            ReplayInput::new(300_000, 0, false, false),
+            ReplayInput::new(0, 1, false, false),
+        ]);
+
+        let results = simulate_holds(
+            &replay,
+            &chart,
+            &create_etterna_windows(4),
+            &create_etterna_tail_rule(),
+        );
+
+        assert_eq!(results[0].tail, Some(TailJudgement::Held));
+        assert_eq!(results[1].tail, Some(TailJudgement::Held));
+        assert_eq!(results[2].tail, Some(TailJudgement::Dropped));
+        assert_eq!(results[3].tail, Some(TailJudgement::Held));
+    }
+
+    #[test]
+    fn test_mine_hits() {
+        let chart = Chart::new(
+            4,
+            vec![
+                Note::mine(1_000_000, 0), // Pressed 50ms before
+                Note::mine(1_000_000, 1), // Held through
+                Note::mine(1_000_000, 2), // Pressed 100ms after
+                Note::mine(1_000_000, 3), // Never touched
+            ],
+        );
+        let replay = ReplayData::new(vec![
+            ReplayInput::new(500_000, 1, true, false),
+            ReplayInput::new(450_000, 0, true, false),
+            ReplayInput::new(10_000, 0, false, false),
+            ReplayInput::new(140_000, 2, true, false),
+            ReplayInput::new(0, 1, false, false),
+        ]);
+
+        assert_eq!(mine_hits(&replay, &chart, 75_000), vec![0, 1]);
+    }
+}
@@ -0,0 +1,320 @@
+//! Runtime-configurable hit windows.
+//!
+//! `OrderedHitWindows` fixes its rule count at compile time and needs a Rust
+//! enum for judgements. `DynamicHitWindows` keeps its rules in a `Vec` and
+//! names its judgements, so custom judgement systems (e.g. a tournament
+//! ruleset) can be loaded from a JSON file:
+//!
+//! ```json
+//! {
+//!     "judgements": [
+//!         { "name": "Perfect", "accuracy_weight": 1.0 },
+//!         { "name": "Good", "accuracy_weight": 0.5 },
+//!         { "name": "Miss", "accuracy_weight": 0.0, "breaks_combo": true }
+//!     ],
+//!     "rules": [
+//!         { "judgement": "Perfect", "early_us": -20000, "late_us": 20000 },
+//!         { "judgement": "Good", "early_us": -80000, "late_us": 100000 }
+//!     ],
+//!     "miss": "Miss",
+//!     "miss_after_us": 100000
+//! }
+//! ```
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::timing::hit_window::{
+    HitRule, HitWindow, HitWindows, judge_rules, rules_late_limit,
+};
+use crate::error::{LeptonError, LeptonResult};
+
+/// Definition of a named judgement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JudgementDefinition {
+    pub name: String,
+
+    /// Accuracy value of the judgement, 1.0 being a perfect hit.
+    #[serde(default)]
+    pub accuracy_weight: f64,
+
+    /// Whether the judgement resets the combo.
+    #[serde(default)]
+    pub breaks_combo: bool,
+}
+
+/// Definition of a single rule: a window mapped to a judgement by name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HitRuleDefinition {
+    pub judgement: String,
+    pub early_us: i64,
+    pub late_us: i64,
+}
+
+/// Serializable definition of a judgement system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HitWindowsDefinition {
+    /// Every judgement of the system, best first.
+    pub judgements: Vec<JudgementDefinition>,
+
+    /// Rules, checked in order.
+    pub rules: Vec<HitRuleDefinition>,
+
+    /// Name of the judgement given to missed notes.
+    pub miss: String,
+
+    /// Late delta after which a press is judged as a miss.
+    #[serde(default)]
+    pub miss_after_us: Option<i64>,
+}
+
+/// Judgement of a `DynamicHitWindows`.
+///
+/// Carries its scoring properties so it can be scored without the windows.
+/// Use `DynamicHitWindows::name` to get its name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicJudgement {
+    /// Index of the judgement in the definition.
+    pub id: usize,
+    pub accuracy_weight: f64,
+    pub breaks_combo: bool,
+}
+
+/// Hit windows with a runtime number of rules and named judgements.
+///
+/// Fields are private so every instance goes through the checks of
+/// `DynamicHitWindows::new`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicHitWindows {
+    judgements: Vec<JudgementDefinition>,
+    rules: Vec<HitRule<DynamicJudgement>>,
+    miss_judgement: DynamicJudgement,
+    miss_after: Option<i64>,
+}
+
+impl DynamicHitWindows {
+    /// Builds hit windows from a definition.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a rule or the miss refers to an unknown judgement,
+    /// if a rule's window ends before it starts (`early_us > late_us`), or if
+    /// a judgement name is defined twice.
+    pub fn new(definition: &HitWindowsDefinition) -> LeptonResult<Self> {
+        let judgements = &definition.judgements;
+        for (index, judgement) in judgements.iter().enumerate() {
+            if judgements[..index].iter().any(|j| j.name == judgement.name) {
+                return Err(LeptonError::Custom(format!(
+                    "Judgement '{}' is defined twice",
+                    judgement.name
+                )));
+            }
+        }
+
+        let find = |name: &str| {
+            find_judgement(judgements, name)
+                .ok_or_else(|| LeptonError::Custom(format!("Unknown judgement '{}'", name)))
+        };
+
+        let rules = definition
+            .rules
+            .iter()
+            .map(|rule| {
+                if rule.early_us > rule.late_us {
+                    return Err(LeptonError::Custom(format!(
+                        "Window of rule '{}' ends before it starts ({}us > {}us)",
+                        rule.judgement, rule.early_us, rule.late_us
+                    )));
+                }
+                Ok(HitRule {
+                    window: HitWindow::new(rule.early_us, rule.late_us),
+                    judgement: find(&rule.judgement)?,
+                })
+            })
+            .collect::<LeptonResult<Vec<_>>>()?;
+
+        Ok(Self {
+            judgements: judgements.clone(),
+            rules,
+            miss_judgement: find(&definition.miss)?,
+            miss_after: definition.miss_after_us,
+        })
+    }
+
+    /// Parses hit windows from a JSON definition.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is invalid or the definition inconsistent.
+    pub fn from_json(json: &str) -> LeptonResult<Self> {
+        let definition: HitWindowsDefinition = serde_json::from_str(json)?;
+        Self::new(&definition)
+    }
+
+    /// Loads hit windows from a JSON definition file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or contains an invalid definition.
+    pub fn from_path(path: impl AsRef<Path>) -> LeptonResult<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+    }
+
+    /// Returns the definition these windows were built from.
+    #[must_use]
+    pub fn definition(&self) -> HitWindowsDefinition {
+        HitWindowsDefinition {
+            judgements: self.judgements.clone(),
+            rules: self
+                .rules
+                .iter()
+                .map(|rule| HitRuleDefinition {
+                    judgement: self.name(rule.judgement).to_string(),
+                    early_us: rule.window.early,
+                    late_us: rule.window.late,
+                })
+                .collect(),
+            miss: self.name(self.miss_judgement).to_string(),
+            miss_after_us: self.miss_after,
+        }
+    }
+
+    /// Serializes these windows to a JSON definition.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> LeptonResult<String> {
+        Ok(serde_json::to_string_pretty(&self.definition())?)
+    }
+
+    /// Returns every judgement of the system, best first.
+    #[must_use]
+    pub fn judgements(&self) -> &[JudgementDefinition] {
+        &self.judgements
+    }
+
+    /// Returns the rules, in the order they are checked.
+    #[must_use]
+    pub fn rules(&self) -> &[HitRule<DynamicJudgement>] {
+        &self.rules
+    }
+
+    /// Returns the late delta after which a press is judged as a miss.
+    #[must_use]
+    pub fn miss_after(&self) -> Option<i64> {
+        self.miss_after
+    }
+
+    /// Returns the name of a judgement.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the judgement does not come from these windows.
+    #[must_use]
+    pub fn name(&self, judgement: DynamicJudgement) -> &str {
+        &self.judgements[judgement.id].name
+    }
+
+    /// Returns the judgement with the given name.
+    #[must_use]
+    pub fn judgement(&self, name: &str) -> Option<DynamicJudgement> {
+        find_judgement(&self.judgements, name)
+    }
+}
+
+/// Finds a judgement of a definition by name.
+fn find_judgement(judgements: &[JudgementDefinition], name: &str) -> Option<DynamicJudgement> {
+    judgements
+        .iter()
+        .position(|j| j.name == name)
+        .map(|id| DynamicJudgement {
+            id,
+            accuracy_weight: judgements[id].accuracy_weight,
+            breaks_combo: judgements[id].breaks_combo,
+        })
+}
+
+impl HitWindows for DynamicHitWindows {
+    type Judgement = DynamicJudgement;
+
+    fn judge(&self, delta_us: i64) -> Option<Self::Judgement> {
+        judge_rules(&self.rules, self.miss_after, self.miss_judgement, delta_us)
+    }
+
+    fn miss(&self) -> Self::Judgement {
+        self.miss_judgement
+    }
+
+    fn late_limit(&self) -> i64 {
+        rules_late_limit(&self.rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOURNAMENT: &str = r#"{
+        "judgements": [
+            { "name": "Perfect", "accuracy_weight": 1.0 },
+            { "name": "Good", "accuracy_weight": 0.5 },
+            { "name": "Miss", "breaks_combo": true }
+        ],
+        "rules": [
+            { "judgement": "Perfect", "early_us": -20000, "late_us": 20000 },
+            { "judgement": "Good", "early_us": -80000, "late_us": 100000 }
+        ],
+        "miss": "Miss",
+        "miss_after_us": 100000
+    }"#;
+
+    #[test]
+    fn test_dynamic_judge() {
+        let windows = DynamicHitWindows::from_json(TOURNAMENT).unwrap();
+        let name = |delta_us| windows.judge(delta_us).map(|j| windows.name(j));
+
+        assert_eq!(name(0), Some("Perfect"));
+        assert_eq!(name(-20_000), Some("Perfect"));
+        assert_eq!(name(20_001), Some("Good"));
+        assert_eq!(name(-80_000), Some("Good"));
+        assert_eq!(name(100_000), Some("Good"));
+        assert_eq!(name(100_001), Some("Miss"));
+        assert_eq!(name(-80_001), None);
+
+        assert_eq!(windows.late_limit(), 100_000);
+        assert_eq!(windows.name(windows.miss()), "Miss");
+        assert!(windows.miss().breaks_combo);
+        assert_eq!(windows.judgement("Good").unwrap().accuracy_weight, 0.5);
+
+        assert_eq!(windows.judgements().len(), 3);
+        assert_eq!(windows.rules()[1].window, HitWindow::new(-80_000, 100_000));
+        assert_eq!(windows.miss_after(), Some(100_000));
+    }
+
+    #[test]
+    fn test_dynamic_json_roundtrip() {
+        let windows = DynamicHitWindows::from_json(TOURNAMENT).unwrap();
+        let json = windows.to_json().unwrap();
+        assert_eq!(DynamicHitWindows::from_json(&json).unwrap(), windows);
+    }
+
+    #[test]
+    fn test_dynamic_invalid_definitions() {
+        let unknown = TOURNAMENT.replace(r#""miss": "Miss""#, r#""miss": "Boo""#);
+        assert!(DynamicHitWindows::from_json(&unknown).is_err());
+
+        let duplicate = TOURNAMENT.replace(r#""name": "Good""#, r#""name": "Perfect""#);
+        assert!(DynamicHitWindows::from_json(&duplicate).is_err());
+
+        let inverted = TOURNAMENT.replace(
+            r#""early_us": -20000, "late_us": 20000"#,
+            r#""early_us": 20000, "late_us": -20000"#,
+        );
+        assert!(DynamicHitWindows::from_json(&inverted).is_err());
+
+        assert!(DynamicHitWindows::from_json("{").is_err());
+    }
+}
@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct HitWindow {
     pub early: i64,
     pub late: i64,
@@ -24,39 +25,100 @@ impl HitWindow {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct HitRule<J> {
     pub window: HitWindow,
     pub judgement: J,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OrderedHitWindows<J, const N: usize> {
     pub rules: [HitRule<J>; N],
     pub miss_judgement: J,
     pub miss_after: Option<i64>,
 }
 
+impl<J: Copy, const N: usize> OrderedHitWindows<J, N> {
+    /// Returns a copy of these windows with every bound multiplied by
+    /// `factor_x100 / 100` (e.g. 150 for 1.5x).
+    #[must_use]
+    pub const fn scaled(&self, factor_x100: i64) -> Self {
+        let mut rules = self.rules;
+        let mut i = 0;
+        while i < N {
+            rules[i].window = HitWindow::new(
+                (rules[i].window.early * factor_x100) / 100,
+                (rules[i].window.late * factor_x100) / 100,
+            );
+            i += 1;
+        }
+
+        let miss_after = match self.miss_after {
+            Some(limit) => Some((limit * factor_x100) / 100),
+            None => None,
+        };
+
+        Self {
+            rules,
+            miss_judgement: self.miss_judgement,
+            miss_after,
+        }
+    }
+}
+
 pub trait HitWindows {
     type Judgement;
     fn judge(&self, delta_us: i64) -> Option<Self::Judgement>;
+
+    /// Judgement given to a note that was never hit.
+    fn miss(&self) -> Self::Judgement;
+
+    /// Latest delta (in microseconds) at which a note can still be hit.
+    /// Past this point the note is considered missed.
+    fn late_limit(&self) -> i64;
+}
+
+/// Judges a delta against rules checked in order, giving `miss_judgement` to
+/// deltas later than `miss_after` that no rule contains.
+pub(crate) fn judge_rules<J: Copy>(
+    rules: &[HitRule<J>],
+    miss_after: Option<i64>,
+    miss_judgement: J,
+    delta_us: i64,
+) -> Option<J> {
+    for rule in rules {
+        if rule.window.contains(delta_us) {
+            return Some(rule.judgement);
+        }
+    }
+
+    if let Some(limit) = miss_after
+        && delta_us > limit
+    {
+        return Some(miss_judgement);
+    }
+
+    None
+}
+
+/// Latest late bound of the rules (0 without rules).
+pub(crate) fn rules_late_limit<J>(rules: &[HitRule<J>]) -> i64 {
+    rules.iter().map(|rule| rule.window.late).max().unwrap_or(0)
 }
 
 impl<J: Copy, const N: usize> HitWindows for OrderedHitWindows<J, N> {
     type Judgement = J;
 
     fn judge(&self, delta_us: i64) -> Option<Self::Judgement> {
-        for rule in &self.rules {
-            if rule.window.contains(delta_us) {
-                return Some(rule.judgement);
-            }
-        }
+        judge_rules(&self.rules, self.miss_after, self.miss_judgement, delta_us)
+    }
 
-        if let Some(limit) = self.miss_after {
-            if delta_us > limit {
-                return Some(self.miss_judgement);
-            }
-        }
+    fn miss(&self) -> Self::Judgement {
+        self.miss_judgement
+    }
 
-        None
+    fn late_limit(&self) -> i64 {
+        rules_late_limit(&self.rules)
     }
 }
 
@@ -78,6 +140,31 @@ mod tests {
         assert!(!window.contains(10_001));
     }
 
+    #[test]
+    fn test_scaled_windows() {
+        let windows = OrderedHitWindows {
+            rules: [
+                HitRule {
+                    window: HitWindow::symmetric(10_000),
+                    judgement: 0,
+                },
+                HitRule {
+                    window: HitWindow::new(-20_000, 30_000),
+                    judgement: 1,
+                },
+            ],
+            miss_judgement: 2,
+            miss_after: Some(30_000),
+        };
+
+        let scaled = windows.scaled(150);
+        assert_eq!(scaled.rules[0].window, HitWindow::symmetric(15_000));
+        assert_eq!(scaled.rules[1].window, HitWindow::new(-30_000, 45_000));
+        assert_eq!(scaled.miss_after, Some(45_000));
+        assert_eq!(scaled.late_limit(), 45_000);
+        assert_eq!(scaled.judge(45_001), Some(2));
+    }
+
     #[test]
     fn test_hit_window_asymmetric() {
         let window = HitWindow::new(-5_000, 10_000);
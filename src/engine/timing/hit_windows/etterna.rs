@@ -1,4 +1,5 @@
 use crate::engine::timing::hit_window::{HitRule, HitWindow, OrderedHitWindows};
+use crate::engine::timing::hold::TailRule;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EtternaJudgement {
@@ -71,6 +72,22 @@ pub const fn create_etterna_windows(judge_level: i64) -> EtternaHitWindows {
     }
 }
 
+/// Maximum time a hold may stay released before it is dropped (250ms).
+pub const ETTERNA_HOLD_GRACE_US: i64 = 250_000;
+
+/// Window around a mine in which a press hits it (75ms).
+pub const ETTERNA_MINE_WINDOW_US: i64 = 75_000;
+
+/// Creates the Etterna long note tail rule.
+///
+/// Hold tails are not timed: the hold must be kept until its end, and
+/// releasing it for longer than `ETTERNA_HOLD_GRACE_US` drops it.
+pub const fn create_etterna_tail_rule() -> TailRule<EtternaHitWindows> {
+    TailRule::HoldDrop {
+        grace_us: ETTERNA_HOLD_GRACE_US,
+    }
+}
+
 impl Default for EtternaHitWindows {
     fn default() -> Self {
         create_etterna_windows(4) // J4 is standard
@@ -90,7 +107,7 @@ mod tests {
         // Marvelous
         assert_eq!(windows.judge(22_500), Some(EtternaJudgement::Marvelous));
         assert_eq!(windows.judge(-22_500), Some(EtternaJudgement::Marvelous));
-        
+
         // Perfect
         assert_eq!(windows.judge(22_501), Some(EtternaJudgement::Perfect));
         assert_eq!(windows.judge(-45_000), Some(EtternaJudgement::Perfect));
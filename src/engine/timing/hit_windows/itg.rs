@@ -0,0 +1,154 @@
+use crate::engine::timing::hit_window::{HitRule, HitWindow, OrderedHitWindows};
+use crate::engine::timing::hold::TailRule;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItgJudgement {
+    /// FA+ inner Fantastic window (blue Fantastic).
+    BlueFantastic,
+    Fantastic,
+    Excellent,
+    Great,
+    Decent,
+    WayOff,
+    Miss,
+}
+
+pub type ItgHitWindows = OrderedHitWindows<ItgJudgement, 5>;
+
+/// ITG windows with the FA+ inner Fantastic window.
+pub type ItgFaPlusHitWindows = OrderedHitWindows<ItgJudgement, 6>;
+
+/// Fixed amount added to every window after scaling (TimingWindowAdd, 1.5ms).
+pub const ITG_TIMING_WINDOW_ADD_US: i64 = 1_500;
+
+/// Maximum time a hold may stay released before it is dropped (320ms).
+pub const ITG_HOLD_GRACE_US: i64 = 320_000;
+
+/// Applies StepMania's window formula: `base * scale + TimingWindowAdd`.
+const fn itg_window(base_us: i64, scale_x100: i64) -> HitWindow {
+    HitWindow::symmetric((base_us * scale_x100) / 100 + ITG_TIMING_WINDOW_ADD_US)
+}
+
+/// Creates ITG Hit Windows based on the timing window scale (100 = 1.0).
+///
+/// Base windows: Fantastic 21.5ms, Excellent 43ms, Great 102ms, Decent 135ms
+/// and Way Off 180ms, each scaled then widened by `ITG_TIMING_WINDOW_ADD_US`.
+pub const fn create_itg_windows(scale_x100: i64) -> ItgHitWindows {
+    let way_off = itg_window(180_000, scale_x100);
+
+    OrderedHitWindows {
+        rules: [
+            HitRule {
+                window: itg_window(21_500, scale_x100),
+                judgement: ItgJudgement::Fantastic,
+            },
+            HitRule {
+                window: itg_window(43_000, scale_x100),
+                judgement: ItgJudgement::Excellent,
+            },
+            HitRule {
+                window: itg_window(102_000, scale_x100),
+                judgement: ItgJudgement::Great,
+            },
+            HitRule {
+                window: itg_window(135_000, scale_x100),
+                judgement: ItgJudgement::Decent,
+            },
+            HitRule {
+                window: way_off,
+                judgement: ItgJudgement::WayOff,
+            },
+        ],
+        miss_judgement: ItgJudgement::Miss,
+        miss_after: Some(way_off.late),
+    }
+}
+
+/// Creates ITG FA+ Hit Windows (Waterfall / Simply Love FA+ mode).
+///
+/// Same as `create_itg_windows`, with a 15ms blue Fantastic window
+/// (13.5ms base + TimingWindowAdd) inside the Fantastic window.
+pub const fn create_itg_fa_plus_windows(scale_x100: i64) -> ItgFaPlusHitWindows {
+    let itg = create_itg_windows(scale_x100);
+
+    OrderedHitWindows {
+        rules: [
+            HitRule {
+                window: itg_window(13_500, scale_x100),
+                judgement: ItgJudgement::BlueFantastic,
+            },
+            itg.rules[0],
+            itg.rules[1],
+            itg.rules[2],
+            itg.rules[3],
+            itg.rules[4],
+        ],
+        miss_judgement: itg.miss_judgement,
+        miss_after: itg.miss_after,
+    }
+}
+
+/// Creates the ITG long note tail rule.
+///
+/// Hold tails are not timed: the hold must be kept until its end, and
+/// releasing it for longer than `ITG_HOLD_GRACE_US` drops it.
+pub const fn create_itg_tail_rule() -> TailRule<ItgHitWindows> {
+    TailRule::HoldDrop {
+        grace_us: ITG_HOLD_GRACE_US,
+    }
+}
+
+impl Default for ItgHitWindows {
+    fn default() -> Self {
+        create_itg_windows(100)
+    }
+}
+
+impl Default for ItgFaPlusHitWindows {
+    fn default() -> Self {
+        create_itg_fa_plus_windows(100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::timing::hit_window::HitWindows;
+
+    #[test]
+    fn test_itg_judgement() {
+        let windows = create_itg_windows(100);
+
+        // Base + 1.5ms TimingWindowAdd
+        assert_eq!(windows.judge(23_000), Some(ItgJudgement::Fantastic));
+        assert_eq!(windows.judge(-23_001), Some(ItgJudgement::Excellent));
+        assert_eq!(windows.judge(44_500), Some(ItgJudgement::Excellent));
+        assert_eq!(windows.judge(103_500), Some(ItgJudgement::Great));
+        assert_eq!(windows.judge(-136_500), Some(ItgJudgement::Decent));
+        assert_eq!(windows.judge(181_500), Some(ItgJudgement::WayOff));
+
+        assert_eq!(windows.judge(181_501), Some(ItgJudgement::Miss));
+        assert_eq!(windows.judge(-181_501), None);
+    }
+
+    #[test]
+    fn test_itg_scale_keeps_fixed_add() {
+        let windows = create_itg_windows(50);
+
+        // 21.5ms * 0.5 + 1.5ms
+        assert_eq!(windows.rules[0].window, HitWindow::symmetric(12_250));
+        // 180ms * 0.5 + 1.5ms
+        assert_eq!(windows.late_limit(), 91_500);
+    }
+
+    #[test]
+    fn test_itg_fa_plus() {
+        let windows = create_itg_fa_plus_windows(100);
+
+        assert_eq!(windows.judge(15_000), Some(ItgJudgement::BlueFantastic));
+        assert_eq!(windows.judge(-15_001), Some(ItgJudgement::Fantastic));
+        assert_eq!(windows.judge(23_000), Some(ItgJudgement::Fantastic));
+        assert_eq!(windows.judge(23_001), Some(ItgJudgement::Excellent));
+        assert_eq!(windows.judge(181_501), Some(ItgJudgement::Miss));
+    }
+}
@@ -1,7 +1,11 @@
 pub mod etterna;
 pub mod guitar_hero;
+pub mod itg;
 pub mod osu;
+pub mod quaver;
 
 pub use etterna::{EtternaHitWindows, EtternaJudgement};
 pub use guitar_hero::{GhHitWindows, GhJudgement};
-pub use osu::{OsuHitWindows, OsuJudgement};
+pub use itg::{ItgFaPlusHitWindows, ItgHitWindows, ItgJudgement};
+pub use osu::{OsuClient, OsuDifficultyMod, OsuHitWindows, OsuJudgement, OsuRulesetVariant};
+pub use quaver::{QuaverHitWindows, QuaverJudgement, QuaverPreset};
@@ -1,4 +1,5 @@
 use crate::engine::timing::hit_window::{HitRule, HitWindow, OrderedHitWindows};
+use crate::engine::timing::hold::TailRule;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OsuJudgement {
@@ -10,52 +11,277 @@ pub enum OsuJudgement {
     Miss,
 }
 
-pub type OsuHitWindows = OrderedHitWindows<OsuJudgement, 5>;
+pub type OsuHitWindows = OrderedHitWindows<OsuJudgement, 6>;
 
-const US_PER_MS: f32 = 1000.0;
+/// osu! client whose hit window formulas and rounding are reproduced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OsuClient {
+    /// osu! stable: windows are floored to whole milliseconds.
+    #[default]
+    Stable,
 
-/// Creates Osu Hit Windows based on the Overall Difficulty (OD).
-pub const fn create_osu_windows(od: f32) -> OsuHitWindows {
-    // Basic scaling for OD (higher OD = stricter windows)
-    let max_us = 16_000;
+    /// osu!lazer: windows are floored to whole milliseconds and widened by half
+    /// a millisecond, so its fractional offsets are judged like stable's whole
+    /// ones.
+    Lazer,
+}
+
+/// Difficulty mods changing the osu!mania hit windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OsuDifficultyMod {
+    #[default]
+    None,
+
+    /// Windows divided by 1.4.
+    HardRock,
+
+    /// Windows multiplied by 1.4.
+    Easy,
+}
+
+/// Ruleset variant selecting which osu!mania hit windows to reproduce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OsuRulesetVariant {
+    pub client: OsuClient,
+
+    /// Beatmap converted from osu!standard. Stable uses fixed windows for
+    /// converts; lazer only does with the Classic mod and otherwise judges
+    /// them like mania-specific beatmaps.
+    pub convert: bool,
+
+    pub difficulty_mod: OsuDifficultyMod,
+
+    /// osu!lazer Classic mod: lazer uses the stable window formulas.
+    /// Ignored by stable.
+    pub classic_mod: bool,
+}
+
+impl OsuRulesetVariant {
+    /// osu! stable on a mania-specific beatmap without mods.
+    pub const STABLE: Self = Self {
+        client: OsuClient::Stable,
+        convert: false,
+        difficulty_mod: OsuDifficultyMod::None,
+        classic_mod: false,
+    };
+
+    /// osu!lazer on a mania-specific beatmap without mods.
+    pub const LAZER: Self = Self {
+        client: OsuClient::Lazer,
+        convert: false,
+        difficulty_mod: OsuDifficultyMod::None,
+        classic_mod: false,
+    };
+
+    /// Returns this variant for a converted beatmap.
+    #[must_use]
+    pub const fn with_convert(self, convert: bool) -> Self {
+        Self { convert, ..self }
+    }
 
-    let od_x10 = (od * 10.0) as i64;
+    /// Returns this variant with a difficulty mod applied.
+    #[must_use]
+    pub const fn with_mod(self, difficulty_mod: OsuDifficultyMod) -> Self {
+        Self {
+            difficulty_mod,
+            ..self
+        }
+    }
 
-    let reduction_us = 300 * od_x10;
+    /// Returns this variant with the osu!lazer Classic mod toggled.
+    #[must_use]
+    pub const fn with_classic_mod(self, classic_mod: bool) -> Self {
+        Self {
+            classic_mod,
+            ..self
+        }
+    }
+}
 
-    let perf_us = 64_000 - reduction_us;
-    let great_us = 97_000 - reduction_us;
-    let good_us = 127_000 - reduction_us;
-    let bad_us = 151_000 - reduction_us;
+/// Window sizes in milliseconds: (MAX, 300, 200, 100, 50, Miss).
+type WindowsMs = [f64; 6];
+
+/// osu! stable windows, computed in single precision like the client.
+const fn stable_windows_ms(od: f32, variant: OsuRulesetVariant) -> WindowsMs {
+    let mut windows = if variant.convert {
+        let (perf, great) = if od > 4.0 { (34.0, 67.0) } else { (47.0, 77.0) };
+        [16.0, perf, great, 97.0, 121.0, 158.0]
+    } else {
+        let reduction = 3.0 * od;
+        [
+            16.0,
+            64.0 - reduction,
+            97.0 - reduction,
+            127.0 - reduction,
+            151.0 - reduction,
+            188.0 - reduction,
+        ]
+    };
+
+    let mut i = 0;
+    while i < windows.len() {
+        windows[i] = match variant.difficulty_mod {
+            OsuDifficultyMod::None => windows[i],
+            OsuDifficultyMod::HardRock => windows[i] / 1.4,
+            OsuDifficultyMod::Easy => windows[i] * 1.4,
+        };
+        windows[i] = windows[i].floor();
+        i += 1;
+    }
+
+    [
+        windows[0] as f64,
+        windows[1] as f64,
+        windows[2] as f64,
+        windows[3] as f64,
+        windows[4] as f64,
+        windows[5] as f64,
+    ]
+}
+
+/// Lazer `DifficultyRange` interpolation: `min` at OD 0, `mid` at OD 5, `max` at OD 10.
+const fn difficulty_range(od: f64, min: f64, mid: f64, max: f64) -> f64 {
+    if od > 5.0 {
+        mid + (max - mid) * (od - 5.0) / 5.0
+    } else if od < 5.0 {
+        mid - (mid - min) * (5.0 - od) / 5.0
+    } else {
+        mid
+    }
+}
+
+/// osu!lazer windows, computed in double precision like the client
+/// (`ManiaHitWindows` in osu.Game.Rulesets.Mania).
+const fn lazer_windows_ms(od: f32, variant: OsuRulesetVariant) -> WindowsMs {
+    let od = od as f64;
+    let multiplier = match variant.difficulty_mod {
+        OsuDifficultyMod::None => 1.0,
+        OsuDifficultyMod::HardRock => 1.0 / 1.4,
+        OsuDifficultyMod::Easy => 1.4,
+    };
+
+    let mut windows = if !variant.classic_mod {
+        [
+            difficulty_range(od, 22.4, 19.4, 13.9),
+            difficulty_range(od, 64.0, 49.0, 34.0),
+            difficulty_range(od, 97.0, 82.0, 67.0),
+            difficulty_range(od, 127.0, 112.0, 97.0),
+            difficulty_range(od, 151.0, 136.0, 121.0),
+            difficulty_range(od, 188.0, 173.0, 158.0),
+        ]
+    } else if variant.convert {
+        // .NET rounds half to even
+        let (perf, great) = if od.round_ties_even() > 4.0 {
+            (34.0, 67.0)
+        } else {
+            (47.0, 77.0)
+        };
+        [16.0, perf, great, 97.0, 121.0, 158.0]
+    } else {
+        let inverted_od = (10.0 - od).clamp(0.0, 10.0);
+        [
+            16.0,
+            34.0 + 3.0 * inverted_od,
+            67.0 + 3.0 * inverted_od,
+            97.0 + 3.0 * inverted_od,
+            121.0 + 3.0 * inverted_od,
+            158.0 + 3.0 * inverted_od,
+        ]
+    };
+
+    let mut i = 0;
+    while i < windows.len() {
+        windows[i] = (windows[i] * multiplier).floor() + 0.5;
+        i += 1;
+    }
+    windows
+}
+
+/// Rounds to the nearest microsecond: interpolated windows like 22.1ms are
+/// slightly below their value in floating point.
+const fn ms_to_us(ms: f64) -> i64 {
+    (ms * 1000.0 + 0.5) as i64
+}
+
+/// Creates Osu Hit Windows based on the Overall Difficulty (OD), as judged
+/// by osu! stable on a mania-specific beatmap without mods.
+pub const fn create_osu_windows(od: f32) -> OsuHitWindows {
+    create_osu_windows_variant(od, OsuRulesetVariant::STABLE)
+}
+
+/// Creates Osu Hit Windows based on the Overall Difficulty (OD) for a
+/// ruleset variant.
+///
+/// Presses earlier than the 50 window but inside the miss window are judged
+/// as a Miss, like in both clients.
+pub const fn create_osu_windows_variant(od: f32, variant: OsuRulesetVariant) -> OsuHitWindows {
+    let windows_ms = match variant.client {
+        OsuClient::Stable => stable_windows_ms(od, variant),
+        OsuClient::Lazer => lazer_windows_ms(od, variant),
+    };
+    let bad_us = ms_to_us(windows_ms[4]);
 
     OrderedHitWindows {
         rules: [
             HitRule {
-                window: HitWindow::symmetric(max_us),
+                window: HitWindow::symmetric(ms_to_us(windows_ms[0])),
                 judgement: OsuJudgement::Marvelous,
             },
             HitRule {
-                window: HitWindow::symmetric(perf_us),
+                window: HitWindow::symmetric(ms_to_us(windows_ms[1])),
                 judgement: OsuJudgement::Perfect,
             },
             HitRule {
-                window: HitWindow::symmetric(great_us),
+                window: HitWindow::symmetric(ms_to_us(windows_ms[2])),
                 judgement: OsuJudgement::Great,
             },
             HitRule {
-                window: HitWindow::symmetric(good_us),
+                window: HitWindow::symmetric(ms_to_us(windows_ms[3])),
                 judgement: OsuJudgement::Good,
             },
             HitRule {
                 window: HitWindow::symmetric(bad_us),
                 judgement: OsuJudgement::Bad,
             },
+            HitRule {
+                window: HitWindow::new(-ms_to_us(windows_ms[5]), bad_us),
+                judgement: OsuJudgement::Miss,
+            },
         ],
         miss_judgement: OsuJudgement::Miss,
         miss_after: Some(bad_us),
     }
 }
 
+/// Creates the long note release windows for the Overall Difficulty (OD).
+/// osu! stable is 1.5x more lenient on releases than on presses.
+pub const fn create_osu_release_windows(od: f32) -> OsuHitWindows {
+    create_osu_release_windows_variant(od, OsuRulesetVariant::STABLE)
+}
+
+/// Creates the long note release windows for a ruleset variant.
+/// Both clients are 1.5x more lenient on releases than on presses.
+pub const fn create_osu_release_windows_variant(
+    od: f32,
+    variant: OsuRulesetVariant,
+) -> OsuHitWindows {
+    create_osu_windows_variant(od, variant).scaled(150)
+}
+
+/// Creates the osu!mania long note tail rule: releases are timed against the
+/// tail with the release windows.
+pub const fn create_osu_tail_rule(od: f32) -> TailRule<OsuHitWindows> {
+    TailRule::Release(create_osu_release_windows(od))
+}
+
+/// Creates the osu!mania long note tail rule for a ruleset variant.
+pub const fn create_osu_tail_rule_variant(
+    od: f32,
+    variant: OsuRulesetVariant,
+) -> TailRule<OsuHitWindows> {
+    TailRule::Release(create_osu_release_windows_variant(od, variant))
+}
+
 impl Default for OsuHitWindows {
     fn default() -> Self {
         create_osu_windows(8.0)
@@ -96,8 +322,129 @@ mod tests {
         assert_eq!(windows.judge(151_001), Some(OsuJudgement::Miss));
         assert_eq!(windows.judge(500_000), Some(OsuJudgement::Miss));
 
+        // Miss range (early)
+        assert_eq!(windows.judge(-151_001), Some(OsuJudgement::Miss));
+        assert_eq!(windows.judge(-188_000), Some(OsuJudgement::Miss));
+
         // Ignore range (too early)
-        assert_eq!(windows.judge(-151_001), None);
+        assert_eq!(windows.judge(-188_001), None);
         assert_eq!(windows.judge(-500_000), None);
     }
+
+    #[test]
+    fn test_osu_stable_floors_windows() {
+        // OD 7.5: 300 = floor(41.5) = 41ms
+        let windows = create_osu_windows(7.5);
+        assert_eq!(windows.judge(41_000), Some(OsuJudgement::Perfect));
+        assert_eq!(windows.judge(41_001), Some(OsuJudgement::Great));
+        assert_eq!(windows.late_limit(), 128_000);
+
+        // HR: MAX = floor(16 / 1.4) = 11ms, 300 = floor(64 / 1.4) = 45ms
+        let hard_rock = create_osu_windows_variant(
+            0.0,
+            OsuRulesetVariant::STABLE.with_mod(OsuDifficultyMod::HardRock),
+        );
+        assert_eq!(hard_rock.judge(11_000), Some(OsuJudgement::Marvelous));
+        assert_eq!(hard_rock.judge(11_001), Some(OsuJudgement::Perfect));
+        assert_eq!(hard_rock.judge(45_001), Some(OsuJudgement::Great));
+
+        // EZ: MAX = floor(16 * 1.4) = 22ms
+        let easy = create_osu_windows_variant(
+            0.0,
+            OsuRulesetVariant::STABLE.with_mod(OsuDifficultyMod::Easy),
+        );
+        assert_eq!(easy.judge(-22_000), Some(OsuJudgement::Marvelous));
+        assert_eq!(easy.judge(-22_001), Some(OsuJudgement::Perfect));
+    }
+
+    #[test]
+    fn test_osu_stable_convert_windows() {
+        let convert = OsuRulesetVariant::STABLE.with_convert(true);
+
+        let low = create_osu_windows_variant(4.0, convert);
+        assert_eq!(low.judge(47_000), Some(OsuJudgement::Perfect));
+        assert_eq!(low.judge(77_000), Some(OsuJudgement::Great));
+
+        let high = create_osu_windows_variant(8.0, convert);
+        assert_eq!(high.judge(34_000), Some(OsuJudgement::Perfect));
+        assert_eq!(high.judge(34_001), Some(OsuJudgement::Great));
+        assert_eq!(high.judge(97_000), Some(OsuJudgement::Good));
+        assert_eq!(high.late_limit(), 121_000);
+        assert_eq!(high.judge(-158_000), Some(OsuJudgement::Miss));
+    }
+
+    #[test]
+    fn test_osu_lazer_windows() {
+        let windows = create_osu_windows_variant(5.0, OsuRulesetVariant::LAZER);
+        assert_eq!(windows.judge(19_500), Some(OsuJudgement::Marvelous));
+        assert_eq!(windows.judge(19_501), Some(OsuJudgement::Perfect));
+        assert_eq!(windows.late_limit(), 136_500);
+
+        // Floored and widened by 0.5ms: OD 7.5 gives a 41.5ms 300 window and
+        // OD 8 a 40.5ms one
+        let windows = create_osu_windows_variant(7.5, OsuRulesetVariant::LAZER);
+        assert_eq!(windows.judge(41_500), Some(OsuJudgement::Perfect));
+        assert_eq!(windows.judge(41_501), Some(OsuJudgement::Great));
+        let od8 = create_osu_windows_variant(8.0, OsuRulesetVariant::LAZER);
+        assert_eq!(od8.judge(-40_500), Some(OsuJudgement::Perfect));
+        assert_eq!(od8.judge(-40_501), Some(OsuJudgement::Great));
+
+        // Without Classic, converts are judged like mania-specific beatmaps
+        let convert = create_osu_windows_variant(7.5, OsuRulesetVariant::LAZER.with_convert(true));
+        assert_eq!(convert, windows);
+    }
+
+    #[test]
+    fn test_osu_lazer_classic_windows() {
+        let classic = OsuRulesetVariant::LAZER.with_classic_mod(true);
+
+        // Stable formulas, widened by 0.5ms: 300 = 64 - 3 * 7.5 = 41.5 -> 41.5ms
+        let windows = create_osu_windows_variant(7.5, classic);
+        assert_eq!(windows.judge(16_500), Some(OsuJudgement::Marvelous));
+        assert_eq!(windows.judge(41_500), Some(OsuJudgement::Perfect));
+        assert_eq!(windows.judge(41_501), Some(OsuJudgement::Great));
+        assert_eq!(windows.late_limit(), 128_500);
+
+        // Converts use the fixed stable windows, OD 4.5 rounding to 4
+        let convert = classic.with_convert(true);
+        let low = create_osu_windows_variant(4.5, convert);
+        assert_eq!(low.judge(47_500), Some(OsuJudgement::Perfect));
+        assert_eq!(low.judge(47_501), Some(OsuJudgement::Great));
+        let high = create_osu_windows_variant(4.6, convert);
+        assert_eq!(high.judge(34_500), Some(OsuJudgement::Perfect));
+        assert_eq!(high.judge(34_501), Some(OsuJudgement::Great));
+        assert_eq!(high.judge(-158_500), Some(OsuJudgement::Miss));
+    }
+
+    #[test]
+    fn test_osu_lazer_fractional_od() {
+        // 22.1ms is 22099.99...µs in floating point
+        assert_eq!(ms_to_us(difficulty_range(0.5, 22.4, 19.4, 13.9)), 22_100);
+        assert_eq!(ms_to_us(difficulty_range(1.0, 22.4, 19.4, 13.9)), 21_800);
+
+        // MAX = floor(22.1) + 0.5 = 22.5ms
+        let windows = create_osu_windows_variant(0.5, OsuRulesetVariant::LAZER);
+        assert_eq!(windows.judge(22_500), Some(OsuJudgement::Marvelous));
+        assert_eq!(windows.judge(22_501), Some(OsuJudgement::Perfect));
+
+        // EZ: MAX = floor(22.4 * 1.4) + 0.5 = 31.5ms
+        let easy = create_osu_windows_variant(
+            0.0,
+            OsuRulesetVariant::LAZER.with_mod(OsuDifficultyMod::Easy),
+        );
+        assert_eq!(easy.judge(-31_500), Some(OsuJudgement::Marvelous));
+        assert_eq!(easy.judge(-31_501), Some(OsuJudgement::Perfect));
+    }
+
+    #[test]
+    fn test_osu_release_windows() {
+        let windows = create_osu_release_windows(0.0);
+
+        assert_eq!(windows.judge(24_000), Some(OsuJudgement::Marvelous));
+        assert_eq!(windows.judge(24_001), Some(OsuJudgement::Perfect));
+        assert_eq!(windows.judge(-226_500), Some(OsuJudgement::Bad));
+        assert_eq!(windows.judge(226_501), Some(OsuJudgement::Miss));
+        assert_eq!(windows.judge(-282_000), Some(OsuJudgement::Miss));
+        assert_eq!(windows.judge(-282_001), None);
+    }
 }
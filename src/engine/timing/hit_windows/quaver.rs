@@ -0,0 +1,127 @@
+use crate::engine::timing::hit_window::{HitRule, HitWindow, OrderedHitWindows};
+use crate::engine::timing::hold::TailRule;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuaverJudgement {
+    Marvelous,
+    Perfect,
+    Great,
+    Good,
+    Okay,
+    Miss,
+}
+
+/// Quaver judgement preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuaverPreset {
+    #[default]
+    Standard,
+    Strict,
+}
+
+pub type QuaverHitWindows = OrderedHitWindows<QuaverJudgement, 6>;
+
+/// Creates Quaver Hit Windows for a judgement preset.
+///
+/// Unlike osu!, pressing inside the miss window (early or late) judges the
+/// note as a Miss instead of being ignored.
+pub const fn create_quaver_windows(preset: QuaverPreset) -> QuaverHitWindows {
+    // (Marvelous, Perfect, Great, Good, Okay, Miss) in ms
+    let (marv_ms, perf_ms, great_ms, good_ms, okay_ms, miss_ms) = match preset {
+        QuaverPreset::Standard => (18, 43, 76, 106, 127, 164),
+        QuaverPreset::Strict => (16, 40, 73, 103, 127, 164),
+    };
+
+    OrderedHitWindows {
+        rules: [
+            HitRule {
+                window: HitWindow::symmetric(marv_ms * 1000),
+                judgement: QuaverJudgement::Marvelous,
+            },
+            HitRule {
+                window: HitWindow::symmetric(perf_ms * 1000),
+                judgement: QuaverJudgement::Perfect,
+            },
+            HitRule {
+                window: HitWindow::symmetric(great_ms * 1000),
+                judgement: QuaverJudgement::Great,
+            },
+            HitRule {
+                window: HitWindow::symmetric(good_ms * 1000),
+                judgement: QuaverJudgement::Good,
+            },
+            HitRule {
+                window: HitWindow::symmetric(okay_ms * 1000),
+                judgement: QuaverJudgement::Okay,
+            },
+            HitRule {
+                window: HitWindow::symmetric(miss_ms * 1000),
+                judgement: QuaverJudgement::Miss,
+            },
+        ],
+        miss_judgement: QuaverJudgement::Miss,
+        miss_after: Some(miss_ms * 1000),
+    }
+}
+
+/// Creates the long note release windows for a judgement preset.
+/// Quaver releases are judged with windows 1.5x larger than presses.
+pub const fn create_quaver_release_windows(preset: QuaverPreset) -> QuaverHitWindows {
+    create_quaver_windows(preset).scaled(150)
+}
+
+/// Creates the Quaver long note tail rule: releases are timed against the
+/// tail with the release windows.
+pub const fn create_quaver_tail_rule(preset: QuaverPreset) -> TailRule<QuaverHitWindows> {
+    TailRule::Release(create_quaver_release_windows(preset))
+}
+
+impl Default for QuaverHitWindows {
+    fn default() -> Self {
+        create_quaver_windows(QuaverPreset::Standard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::timing::hit_window::HitWindows;
+
+    #[test]
+    fn test_quaver_judgement_standard() {
+        let windows = create_quaver_windows(QuaverPreset::Standard);
+
+        assert_eq!(windows.judge(18_000), Some(QuaverJudgement::Marvelous));
+        assert_eq!(windows.judge(-18_001), Some(QuaverJudgement::Perfect));
+        assert_eq!(windows.judge(76_000), Some(QuaverJudgement::Great));
+        assert_eq!(windows.judge(-106_000), Some(QuaverJudgement::Good));
+        assert_eq!(windows.judge(127_000), Some(QuaverJudgement::Okay));
+
+        // Early and late presses inside the miss window are misses
+        assert_eq!(windows.judge(-164_000), Some(QuaverJudgement::Miss));
+        assert_eq!(windows.judge(164_000), Some(QuaverJudgement::Miss));
+
+        // Too early presses are ignored
+        assert_eq!(windows.judge(-164_001), None);
+        assert_eq!(windows.late_limit(), 164_000);
+    }
+
+    #[test]
+    fn test_quaver_judgement_strict() {
+        let windows = create_quaver_windows(QuaverPreset::Strict);
+
+        assert_eq!(windows.judge(16_000), Some(QuaverJudgement::Marvelous));
+        assert_eq!(windows.judge(18_000), Some(QuaverJudgement::Perfect));
+        assert_eq!(windows.judge(-75_000), Some(QuaverJudgement::Good));
+    }
+
+    #[test]
+    fn test_quaver_release_windows() {
+        let windows = create_quaver_release_windows(QuaverPreset::Standard);
+
+        assert_eq!(windows.judge(27_000), Some(QuaverJudgement::Marvelous));
+        assert_eq!(windows.judge(27_001), Some(QuaverJudgement::Perfect));
+        assert_eq!(windows.judge(190_500), Some(QuaverJudgement::Okay));
+        assert_eq!(windows.judge(246_000), Some(QuaverJudgement::Miss));
+    }
+}
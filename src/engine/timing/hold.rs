@@ -0,0 +1,94 @@
+//! Long note (hold) tail judging rules.
+
+use crate::engine::timing::hit_window::HitWindows;
+
+/// How the tail of a long note is judged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TailRule<R> {
+    /// The release is timed against the tail and judged by dedicated
+    /// release windows (osu!, Quaver).
+    ///
+    /// Releasing before the release windows breaks the note (miss).
+    /// Holding past them is judged at their late edge.
+    Release(R),
+
+    /// Only the hold state matters (Etterna / StepMania).
+    ///
+    /// The note may be released and grabbed again within `grace_us`.
+    /// Staying released longer than that before the tail drops the note.
+    HoldDrop {
+        /// Maximum time in microseconds the note may stay released.
+        grace_us: i64,
+    },
+}
+
+/// Judgement of a long note tail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TailJudgement<J> {
+    /// Release judged by the release windows of a `TailRule::Release`.
+    Release {
+        judgement: J,
+
+        /// Release offset in microseconds (release time - tail time).
+        /// `None` if the note was never released inside the windows.
+        delta_us: Option<i64>,
+    },
+
+    /// The note was held until its end (`TailRule::HoldDrop`).
+    Held,
+
+    /// The note was let go or never grabbed (`TailRule::HoldDrop`).
+    Dropped,
+}
+
+impl<R: HitWindows> TailRule<R>
+where
+    R::Judgement: Copy,
+{
+    /// Judges a release happening `delta_us` after the tail of a held note.
+    ///
+    /// Returns `None` when the rule keeps the note going (`HoldDrop` releases
+    /// that may still be grabbed again).
+    pub(crate) fn judge_release(&self, delta_us: i64) -> Option<TailJudgement<R::Judgement>> {
+        match self {
+            TailRule::Release(windows) => {
+                let judgement = windows.judge(delta_us).unwrap_or(windows.miss());
+                Some(TailJudgement::Release {
+                    judgement,
+                    delta_us: Some(delta_us),
+                })
+            }
+            TailRule::HoldDrop { grace_us } => {
+                if delta_us + grace_us >= 0 {
+                    Some(TailJudgement::Held)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Tail judgement of a note held past the late edge of the release windows.
+    pub(crate) fn held_past(&self) -> TailJudgement<R::Judgement> {
+        match self {
+            TailRule::Release(windows) => TailJudgement::Release {
+                judgement: windows
+                    .judge(windows.late_limit())
+                    .unwrap_or(windows.miss()),
+                delta_us: None,
+            },
+            TailRule::HoldDrop { .. } => TailJudgement::Held,
+        }
+    }
+
+    /// Tail judgement of a note whose head was never hit.
+    pub(crate) fn missed(&self) -> TailJudgement<R::Judgement> {
+        match self {
+            TailRule::Release(windows) => TailJudgement::Release {
+                judgement: windows.miss(),
+                delta_us: None,
+            },
+            TailRule::HoldDrop { .. } => TailJudgement::Dropped,
+        }
+    }
+}
@@ -1,4 +1,8 @@
+pub mod dynamic_hit_windows;
 pub mod hit_window;
 pub mod hit_windows;
+pub mod hold;
 
-pub use hit_window::{HitWindow, HitWindows};
+pub use dynamic_hit_windows::{DynamicHitWindows, DynamicJudgement, HitWindowsDefinition};
+pub use hit_window::{HitRule, HitWindow, HitWindows, OrderedHitWindows};
+pub use hold::{TailJudgement, TailRule};
@@ -14,9 +14,24 @@ pub enum LeptonError {
     #[error("Zstd error: {0}")]
     Zstd(std::io::Error), // Remove #[from] to avoid conflict with Io(#[from] std::io::Error)
 
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
     #[error("Invalid magic bytes")]
     InvalidMagic,
 
+    #[error("Unsupported LEP version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Checksum mismatch: the file is truncated or corrupted")]
+    ChecksumMismatch,
+
+    #[error("Invalid signature: the replay was modified or signed with another key")]
+    InvalidSignature,
+
+    #[error("Replay is not signed")]
+    MissingSignature,
+
     #[error("Custom error: {0}")]
     Custom(String),
 }
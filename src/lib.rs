@@ -1,3 +1,4 @@
 pub mod codec;
+pub mod engine;
 pub mod error;
 pub mod model;
@@ -0,0 +1,255 @@
+//! Chart data structure.
+
+use crate::error::{LeptonError, LeptonResult};
+
+/// Kind of a chart note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub enum NoteKind {
+    /// A single note to be pressed.
+    Tap,
+
+    /// A long note, held from the note time until `end_us`.
+    Hold {
+        /// End of the hold in microseconds since the start of the chart.
+        end_us: i64,
+    },
+
+    /// A note that must not be hit.
+    Mine,
+}
+
+/// A single chart note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct Note {
+    /// Time of the note in microseconds since the start of the chart.
+    /// For long notes, this is the time of the head.
+    pub time_us: i64,
+
+    /// Column index (0-15).
+    pub column: u8,
+
+    /// Kind of the note.
+    pub kind: NoteKind,
+}
+
+impl Note {
+    /// Creates a tap note.
+    #[must_use]
+    pub const fn tap(time_us: i64, column: u8) -> Self {
+        Self {
+            time_us,
+            column,
+            kind: NoteKind::Tap,
+        }
+    }
+
+    /// Creates a long note going from `start_us` to `end_us`.
+    #[must_use]
+    pub const fn hold(start_us: i64, end_us: i64, column: u8) -> Self {
+        Self {
+            time_us: start_us,
+            column,
+            kind: NoteKind::Hold { end_us },
+        }
+    }
+
+    /// Creates a mine.
+    #[must_use]
+    pub const fn mine(time_us: i64, column: u8) -> Self {
+        Self {
+            time_us,
+            column,
+            kind: NoteKind::Mine,
+        }
+    }
+
+    /// Returns the end time of the note.
+    /// Equal to `time_us` for everything but long notes.
+    #[inline]
+    #[must_use]
+    pub const fn end_us(&self) -> i64 {
+        match self.kind {
+            NoteKind::Hold { end_us } => end_us,
+            _ => self.time_us,
+        }
+    }
+
+    /// Checks if this note is a long note.
+    #[inline]
+    #[must_use]
+    pub const fn is_hold(&self) -> bool {
+        matches!(self.kind, NoteKind::Hold { .. })
+    }
+
+    /// Checks if this note is a mine.
+    #[inline]
+    #[must_use]
+    pub const fn is_mine(&self) -> bool {
+        matches!(self.kind, NoteKind::Mine)
+    }
+}
+
+/// Notes of a chart, as expected to be played by a replay.
+///
+/// All times are integer microseconds since the start of the chart,
+/// matching the engine timing model.
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct Chart {
+    /// Number of columns (keys) of the chart (1-16).
+    pub column_count: u8,
+
+    /// Optional hash of the chart.
+    /// Matches `ReplayData::hash` for replays played on this chart.
+    pub hash: Option<String>,
+
+    /// List of notes, sorted by time.
+    pub notes: Vec<Note>,
+}
+
+impl Chart {
+    /// Creates a new chart, sorting notes by time.
+    ///
+    /// # Arguments
+    ///
+    /// * `column_count` - Number of columns of the chart
+    /// * `notes` - List of notes
+    #[must_use]
+    pub fn new(column_count: u8, mut notes: Vec<Note>) -> Self {
+        notes.sort_by_key(|note| (note.time_us, note.column));
+        Self {
+            column_count,
+            hash: None,
+            notes,
+        }
+    }
+
+    /// Creates a chart linked to a hash.
+    #[must_use]
+    pub fn with_hash(column_count: u8, hash: Option<String>, notes: Vec<Note>) -> Self {
+        Self {
+            hash,
+            ..Self::new(column_count, notes)
+        }
+    }
+
+    /// Returns the number of notes the player has to hit (taps and long notes).
+    #[must_use]
+    pub fn hittable_count(&self) -> usize {
+        self.notes.iter().filter(|note| !note.is_mine()).count()
+    }
+
+    /// Returns the number of long notes.
+    #[must_use]
+    pub fn hold_count(&self) -> usize {
+        self.notes.iter().filter(|note| note.is_hold()).count()
+    }
+
+    /// Returns the number of mines.
+    #[must_use]
+    pub fn mine_count(&self) -> usize {
+        self.notes.iter().filter(|note| note.is_mine()).count()
+    }
+
+    /// Checks that the chart is consistent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column count exceeds 16, a note is outside the
+    /// column range, or a long note ends before it starts.
+    pub fn validate(&self) -> LeptonResult<()> {
+        if self.column_count == 0 || self.column_count > 16 {
+            return Err(LeptonError::Custom(format!(
+                "Invalid column count: {} (must be 1-16)",
+                self.column_count
+            )));
+        }
+
+        for (index, note) in self.notes.iter().enumerate() {
+            if note.column >= self.column_count {
+                return Err(LeptonError::Custom(format!(
+                    "Note {} is on column {} but chart has {} columns",
+                    index, note.column, self.column_count
+                )));
+            }
+            if note.end_us() < note.time_us {
+                return Err(LeptonError::Custom(format!(
+                    "Long note {} ends before it starts",
+                    index
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notes_are_sorted() {
+        let chart = Chart::new(
+            4,
+            vec![
+                Note::tap(2_000, 1),
+                Note::tap(1_000, 3),
+                Note::tap(1_000, 0),
+            ],
+        );
+        let times: Vec<(i64, u8)> = chart.notes.iter().map(|n| (n.time_us, n.column)).collect();
+        assert_eq!(times, vec![(1_000, 0), (1_000, 3), (2_000, 1)]);
+    }
+
+    #[test]
+    fn test_counts() {
+        let chart = Chart::new(
+            4,
+            vec![
+                Note::tap(0, 0),
+                Note::hold(1_000, 5_000, 1),
+                Note::mine(2_000, 2),
+            ],
+        );
+        assert_eq!(chart.hittable_count(), 2);
+        assert_eq!(chart.hold_count(), 1);
+        assert_eq!(chart.mine_count(), 1);
+        assert_eq!(chart.notes[1].end_us(), 5_000);
+        assert_eq!(chart.notes[0].end_us(), 0);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Chart::new(4, vec![Note::tap(0, 3)]).validate().is_ok());
+        assert!(Chart::new(4, vec![Note::tap(0, 4)]).validate().is_err());
+        assert!(Chart::new(17, vec![]).validate().is_err());
+        assert!(
+            Chart::new(4, vec![Note::hold(5_000, 1_000, 0)])
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rkyv_roundtrip() {
+        let chart = Chart::with_hash(
+            7,
+            Some("chart_hash".to_string()),
+            vec![
+                Note::tap(0, 0),
+                Note::hold(1_000, 5_000, 6),
+                Note::mine(2_000, 3),
+            ],
+        );
+
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&chart).unwrap();
+        let archived = rkyv::access::<ArchivedChart, rkyv::rancor::Error>(&bytes).unwrap();
+        assert_eq!(archived, &chart);
+
+        let deserialized = rkyv::deserialize::<Chart, rkyv::rancor::Error>(archived).unwrap();
+        assert_eq!(deserialized, chart);
+    }
+}
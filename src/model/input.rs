@@ -102,8 +102,8 @@ mod tests {
         };
         let (col, press, auto) = input.unpack();
         assert_eq!(col, 7);
-        assert_eq!(press, true);
-        assert_eq!(auto, false);
+        assert!(press);
+        assert!(!auto);
     }
 
     #[test]
@@ -123,8 +123,8 @@ mod tests {
     fn test_all_flags() {
         let input = ReplayInput::new(500, 3, true, true);
         assert_eq!(input.column(), 3);
-        assert_eq!(input.is_press(), true);
-        assert_eq!(input.is_auto(), true);
+        assert!(input.is_press());
+        assert!(input.is_auto());
         assert_eq!(input.delta_us, 500);
     }
 
@@ -0,0 +1,28 @@
+//! Per-note judgements recorded with a replay.
+
+/// Judgement given to one note of the chart, as recorded by the source game
+/// or computed by the simulator.
+#[derive(Debug, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct RecordedJudgement {
+    /// Index of the note in `Chart::notes`.
+    pub note_index: u32,
+
+    /// Judgement name (e.g. `"300"`, `"Marvelous"`).
+    pub judgement: String,
+
+    /// Hit offset in microseconds (input time - note time).
+    /// `None` if the note was never hit.
+    pub offset_us: Option<i64>,
+}
+
+impl RecordedJudgement {
+    #[must_use]
+    pub fn new(note_index: u32, judgement: impl Into<String>, offset_us: Option<i64>) -> Self {
+        Self {
+            note_index,
+            judgement: judgement.into(),
+            offset_us,
+        }
+    }
+}
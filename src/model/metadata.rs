@@ -0,0 +1,96 @@
+//! Replay metadata: who played, when, on which game and with which result.
+
+/// Number of times a judgement was given, by name (e.g. `"300"`, `"Marvelous"`).
+#[derive(Debug, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct JudgementCount {
+    pub name: String,
+    pub count: u32,
+}
+
+impl JudgementCount {
+    #[must_use]
+    pub fn new(name: impl Into<String>, count: u32) -> Self {
+        Self {
+            name: name.into(),
+            count,
+        }
+    }
+}
+
+/// Scoreboard result of a play, as recorded by the source game.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Default, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct ScoreSummary {
+    pub score: u64,
+    pub max_combo: u32,
+
+    /// Full combo flag.
+    pub perfect: bool,
+
+    /// Judgement counts, in the source game's order.
+    pub judgements: Vec<JudgementCount>,
+}
+
+impl ScoreSummary {
+    /// Returns the count of the judgement with the given name (0 if absent).
+    #[must_use]
+    pub fn count(&self, name: &str) -> u32 {
+        self.judgements
+            .iter()
+            .find(|judgement| judgement.name == name)
+            .map_or(0, |judgement| judgement.count)
+    }
+}
+
+/// A point of a health graph.
+#[derive(Debug, Clone, Copy, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct HealthPoint {
+    /// Time in microseconds since the start of the chart.
+    pub time_us: i64,
+
+    /// Health from 0.0 (empty) to 1.0 (full).
+    pub health: f64,
+}
+
+/// Game-independent replay metadata.
+///
+/// Every field is optional so formats only fill what they know.
+#[derive(Debug, Clone, PartialEq, Default, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct ReplayMetadata {
+    /// Name of the player.
+    pub player: Option<String>,
+
+    /// Time of the play in nanoseconds since the Unix epoch.
+    pub timestamp_ns: Option<i64>,
+
+    /// Name of the game the replay was recorded in (e.g. `"osu!"`).
+    pub game: Option<String>,
+
+    /// Version of the game the replay was recorded in.
+    pub game_version: Option<String>,
+
+    /// Mods as acronyms of the source game (e.g. `["HD", "DT"]`).
+    pub mods: Vec<String>,
+
+    /// Online identifier of the score.
+    pub online_id: Option<i64>,
+
+    /// Result of the play.
+    pub score: Option<ScoreSummary>,
+
+    /// Health over the play, in chronological order.
+    pub health_graph: Vec<HealthPoint>,
+}
+
+impl ReplayMetadata {
+    /// Checks if no metadata is set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
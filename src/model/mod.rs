@@ -1,5 +1,11 @@
+pub mod chart;
 pub mod input;
+pub mod judgement;
+pub mod metadata;
 pub mod replay;
 
+pub use chart::{Chart, Note, NoteKind};
 pub use input::ReplayInput;
+pub use judgement::RecordedJudgement;
+pub use metadata::{HealthPoint, JudgementCount, ReplayMetadata, ScoreSummary};
 pub use replay::ReplayData;
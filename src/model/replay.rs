@@ -1,6 +1,10 @@
 //! Replay data structure.
 
+use std::collections::BTreeMap;
+
 use crate::model::input::ReplayInput;
+use crate::model::judgement::RecordedJudgement;
+use crate::model::metadata::ReplayMetadata;
 
 /// Complete replay data in LEP format.
 ///
@@ -11,9 +15,13 @@ use crate::model::input::ReplayInput;
 /// The LEP format encodes data compactly:
 /// - Magic bytes: `"LEP\0"` (4 bytes)
 /// - Version: 1 byte
-/// - Rate: f64 little-endian (8 bytes)
-/// - Hash: LEB128 length + UTF-8 string
-/// - Inputs: LEB128 count + packed inputs avec delta LEB128
+/// - Sections: tag byte + LEB128 length + payload, for the header (rate,
+///   hash, start offset), metadata, inputs, judgements and extensions.
+///   Readers skip sections they do not know.
+/// - Checksum: last section, CRC32C of everything before it
+///
+/// Version 1 files (fixed layout: rate, hash, inputs, trailing metadata and
+/// start offset) are still decoded.
 ///
 /// # Compression
 ///
@@ -21,13 +29,16 @@ use crate::model::input::ReplayInput;
 #[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(compare(PartialEq), derive(Debug))]
 pub struct ReplayData {
-    /// Version of the LEP format (currently 1).
+    /// Version of the LEP format (currently 3).
     pub version: u8,
 
     /// Replay rate.
     /// - 1.0 = normal speed
     /// - 0.5 = half speed
     /// - 2.0 = double speed
+    ///
+    /// Input deltas are in song time, not wall-clock time: they line up with
+    /// the chart whatever the rate.
     pub rate: f64,
 
     /// Optional hash of the chart associated with the replay.
@@ -37,6 +48,19 @@ pub struct ReplayData {
     /// List of replay inputs.
     /// Inputs are stored with deltas in microseconds.
     pub inputs: Vec<ReplayInput>,
+
+    /// Chart time in microseconds the first input delta is counted from.
+    /// Negative when the replay starts before the chart (osu! lead-in).
+    pub start_offset_us: i64,
+
+    /// Player, date, game and score information of the play.
+    pub metadata: ReplayMetadata,
+
+    /// Per-note judgements of the play, empty if not recorded.
+    pub judgements: Vec<RecordedJudgement>,
+
+    /// Application data by name, kept as opaque bytes.
+    pub extensions: BTreeMap<String, Vec<u8>>,
 }
 
 impl ReplayData {
@@ -48,10 +72,14 @@ impl ReplayData {
     #[must_use]
     pub fn new(inputs: Vec<ReplayInput>) -> Self {
         Self {
-            version: 1,
+            version: 3,
             rate: 1.0,
             hash: None,
             inputs,
+            start_offset_us: 0,
+            metadata: ReplayMetadata::default(),
+            judgements: Vec::new(),
+            extensions: BTreeMap::new(),
         }
     }
 
@@ -59,10 +87,35 @@ impl ReplayData {
     #[must_use]
     pub fn with_params(rate: f64, hash: Option<String>, inputs: Vec<ReplayInput>) -> Self {
         Self {
-            version: 1,
+            version: 3,
             rate,
             hash,
             inputs,
+            start_offset_us: 0,
+            metadata: ReplayMetadata::default(),
+            judgements: Vec::new(),
+            extensions: BTreeMap::new(),
+        }
+    }
+
+    /// Returns this replay with the given start offset.
+    #[must_use]
+    pub fn with_start_offset(self, start_offset_us: i64) -> Self {
+        Self {
+            start_offset_us,
+            ..self
         }
     }
+
+    /// Returns this replay with the given metadata.
+    #[must_use]
+    pub fn with_metadata(self, metadata: ReplayMetadata) -> Self {
+        Self { metadata, ..self }
+    }
+
+    /// Returns this replay with the given per-note judgements.
+    #[must_use]
+    pub fn with_judgements(self, judgements: Vec<RecordedJudgement>) -> Self {
+        Self { judgements, ..self }
+    }
 }